@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// The options set by the launcher itself, which are not forwarded to the workers as they are.
+const LAUNCHER_OPTIONS: &[(&str, Option<char>)] = &[
+    ("--launch", None),
+    ("--peer-id", Some('p')),
+    ("--delay-startup", Some('d')),
+];
+
+/// Removes the launcher options from the command line `args` of the launcher (without the program
+/// name), keeping the arguments shared by all the workers.
+fn shared_args(args: &[String]) -> Vec<String> {
+    let mut shared = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let option = LAUNCHER_OPTIONS.iter().find(|(long, short)| {
+            arg == long
+                || arg.starts_with(&format!("{}=", long))
+                || matches!(short, Some(short) if arg.starts_with(&format!("-{}", short)))
+        });
+        match option {
+            // `--option value` and `-o value`, the value is skipped too.
+            Some((long, short))
+                if arg == long || matches!(short, Some(short) if arg == &format!("-{}", short)) =>
+            {
+                args.next();
+            }
+            // `--option=value` and `-ovalue`.
+            Some(_) => {}
+            None => shared.push(arg.clone()),
+        }
+    }
+    shared
+}
+
+/// Kills the `workers` still running and waits for them to exit.
+fn kill_workers(workers: &mut [(usize, Child)]) {
+    for (_, child) in workers.iter_mut() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Spawns the worker `peer_id` with the `args` shared by all the workers, its output redirected
+/// into `log_dir`.
+fn spawn_worker(
+    program: &Path,
+    args: &[String],
+    peer_id: usize,
+    delay_startup: u64,
+    log_dir: &Path,
+) -> anyhow::Result<Child> {
+    let stdout = File::create(log_dir.join(format!("peer_{}_stdout.log", peer_id)))?;
+    let stderr = File::create(log_dir.join(format!("peer_{}_stderr.log", peer_id)))?;
+    let child = Command::new(program)
+        .args(args)
+        .arg("--peer-id")
+        .arg(peer_id.to_string())
+        .arg("--delay-startup")
+        .arg(delay_startup.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(stdout))
+        .stderr(Stdio::from(stderr))
+        .spawn()?;
+    Ok(child)
+}
+
+/// Spawns `worker_num` worker processes with the peer ids `first_peer_id..first_peer_id + worker_num`
+/// and the command line arguments of this process, and waits for all of them to exit.
+///
+/// The stdout and stderr of every worker are written into `peer_<peer id>_stdout.log` and
+/// `peer_<peer id>_stderr.log` in `log_dir`. The startup delay of every worker is increased by the
+/// time elapsed since the first one was spawned, so that their fixed startup waits end together.
+/// If a worker fails, or cannot be spawned, the remaining ones are killed and an error is returned.
+pub fn launch_workers(
+    worker_num: usize,
+    first_peer_id: usize,
+    delay_startup: u64,
+    log_dir: &Path,
+) -> anyhow::Result<()> {
+    let program = std::env::current_exe()?;
+    let args = shared_args(&std::env::args().skip(1).collect::<Vec<_>>());
+    let launch_start = Instant::now();
+    let mut workers: Vec<(usize, Child)> = vec![];
+    for peer_id in first_peer_id..first_peer_id + worker_num {
+        let delay = delay_startup + launch_start.elapsed().as_millis() as u64;
+        match spawn_worker(&program, &args, peer_id, delay, log_dir) {
+            Ok(child) => {
+                println!("Launched peer {} (pid {})", peer_id, child.id());
+                workers.push((peer_id, child));
+            }
+            Err(e) => {
+                kill_workers(&mut workers);
+                return Err(e.context(format!("failed to launch peer {}", peer_id)));
+            }
+        }
+    }
+
+    while !workers.is_empty() {
+        let mut failed = None;
+        let mut running = vec![];
+        for (peer_id, mut child) in workers {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => println!("Peer {} exited", peer_id),
+                Ok(Some(status)) => {
+                    eprintln!("Peer {} failed: {}", peer_id, status);
+                    failed = Some((peer_id, status.to_string()));
+                }
+                Ok(None) => running.push((peer_id, child)),
+                Err(e) => {
+                    eprintln!("Peer {} cannot be waited for: {}", peer_id, e);
+                    running.push((peer_id, child));
+                    failed = Some((peer_id, e.to_string()));
+                }
+            }
+        }
+        if let Some((peer_id, reason)) = failed {
+            kill_workers(&mut running);
+            anyhow::bail!(
+                "peer {} failed ({}), see {}",
+                peer_id,
+                reason,
+                log_dir
+                    .join(format!("peer_{}_stderr.log", peer_id))
+                    .display()
+            );
+        }
+        workers = running;
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
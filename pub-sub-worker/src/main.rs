@@ -1,10 +1,16 @@
-mod common;
-mod utils;
-mod workers;
-use common::*;
+mod launcher;
+
+use clap::Parser;
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::Write;
 use std::path::PathBuf;
-use utils::*;
-use workers::*;
+use zenoh_perf_core::{
+    collector_worker, common::*, coordinator_worker, open_session, pub_and_sub_worker,
+    publish_result, wait_for_start, KeyTopology, PacingMode, PayloadFiller, PublisherQos,
+    ResourceSampler, RoundParams, SentMsgNums, SessionMode, SubReport, SubscriptionKind, TestMode,
+    WorkerConfig, ZenohConfigFiles, ZenohConfigs,
+};
 
 #[derive(Debug, Parser, Serialize, Deserialize, Clone)]
 #[clap(author, version, about, long_about = None)]
@@ -12,9 +18,14 @@ pub struct Cli {
     #[clap(short = 'o', long, default_value = "./", parse(from_os_str))]
     /// The path to store the output .json file.
     output_dir: PathBuf,
-    #[clap(short = 'p', long)]
-    /// The peer ID for this process
-    peer_id: usize,
+    #[clap(
+        short = 'p',
+        long,
+        required_unless_present_any = &["coordinator-for", "launch"]
+    )]
+    /// The peer ID for this process.
+    /// With `--launch`, the peer ID of the first worker process (0 if not set).
+    peer_id: Option<usize>,
     #[clap(short = 'a', long)]
     /// The total number of publisher peers.
     /// If pub-sub-separate flag not used, this will be the total number of peers.
@@ -29,9 +40,13 @@ pub struct Cli {
     #[clap(short = 'm', long, default_value = "1")]
     /// The number of messages each publisher peer will try to send.
     num_msgs_per_peer: usize,
-    #[clap(short = 'n', long, default_value = "8")]
+    #[clap(short = 'n', long, default_value = "40")]
     /// The payload size (bytes) of the message.
+    /// The first 40 bytes carry the binary header (publisher id, sequence number, send timestamp, etc.).
     payload_size: usize,
+    #[clap(long, arg_enum, default_value = "dot")]
+    /// The bytes used to fill the payload after the header.
+    payload_filler: PayloadFiller,
     #[clap(long)]
     /// Create multiple zenoh runtimes on a single machine or not for each peer.
     /// It will always be set to false if pub_sub_sep is not set, since the worker will generate a new zenoh instance for each pair of pub and sub worker.
@@ -50,7 +65,39 @@ pub struct Cli {
     /// Note that the num_msgs_per_peer needs to be the same on both remote and local machines
     remote_pub_peers: usize,
     #[clap(short = 'd', long, default_value = "0")]
+    /// Shortens the fixed startup wait of this process. Unit: milliseconds (ms).
+    /// Ignored with `--coordinated`.
     delay_startup: u64,
+    #[clap(long)]
+    /// Spawn this number of worker processes on this machine with consecutive peer IDs and the other arguments of this process,
+    /// and wait for all of them to exit. Their stdout and stderr are written into `peer_<peer id>_std{out,err}.log` in output_dir.
+    /// Exits with an error as soon as a worker fails.
+    launch: Option<usize>,
+    #[clap(long)]
+    /// Wait for the start of the round from a coordinator instead of a fixed startup wait.
+    /// The round parameters (init_time, round_timeout, num_msgs_per_peer, payload_size, throughput_duration)
+    /// of the coordinator replace the ones of this process.
+    coordinated: bool,
+    #[clap(long)]
+    /// Run as the coordinator of this number of `--coordinated` worker processes instead of as a worker:
+    /// wait until all of them are ready, then send them the start of the round and the round parameters of this process.
+    coordinator_for: Option<usize>,
+    #[clap(long, default_value = "1000")]
+    /// The time between the moment all the workers are ready and the start of the round, with `--coordinator-for`.
+    /// Unit: milliseconds (ms).
+    start_delay: u64,
+    #[clap(long)]
+    /// With `--coordinator-for`, also collect the results the workers publish at the end of the round,
+    /// and write them into a single `Exp_*.json` file in output_dir.
+    collect: bool,
+    #[clap(long, default_value = "10000")]
+    /// How long the collector waits for the results after the end of the round. Unit: milliseconds (ms).
+    collect_timeout: u64,
+    #[clap(long, arg_enum, default_value = "interval")]
+    /// How publishers space out their messages.
+    /// `interval` sleeps pub_interval after every pub_interval_freq messages,
+    /// the other modes send at pub_rate (constant, token-bucket with bursts of burst_size, or poisson arrivals).
+    pacing: PacingMode,
     #[clap(long, default_value = "1")]
     /// The interval between the messages published by publisher. (Unit: ms)
     pub pub_interval: u64,
@@ -58,42 +105,249 @@ pub struct Cli {
     /// The frequency to add the pub_interval. (Unit: messages/times)
     /// If not specified, it is turned off. (Not pub_interval will be used)
     pub pub_interval_freq: usize,
+    #[clap(long)]
+    /// The rate each publisher sends at with the constant, token-bucket and poisson pacings. Unit: messages/s.
+    pub_rate: Option<f64>,
+    #[clap(long, default_value = "1")]
+    /// The maximum number of messages sent back to back with the token-bucket pacing.
+    burst_size: usize,
+    #[clap(long)]
+    /// Busy-wait the last 2 ms before every deadline of the constant, token-bucket and poisson pacings,
+    /// for a sub-millisecond accuracy at the cost of a busy core per publisher.
+    spin_wait: bool,
+    #[clap(long, arg_enum, default_value = "shared")]
+    /// How the keys of the publishers are laid out: `shared` puts on a single key (`/demo/example/hello`),
+    /// `per-publisher` puts on one key per publisher (`/bench/<peer id>`),
+    /// `topics` spreads the publishers and subscribers over topic_num topics (`/bench/topic/<topic>`).
+    key_topology: KeyTopology,
+    #[clap(long, arg_enum, default_value = "wildcard")]
+    /// Whether subscribers declare a single wildcard subscription or one subscription for each exact key.
+    subscription: SubscriptionKind,
+    #[clap(long, default_value = "1")]
+    /// The number of topics with the `topics` key topology.
+    topic_num: usize,
+    #[clap(long, default_value = "0")]
+    /// The number of intermediate levels (`/l0/l1/...`) inserted in every key, to test deep hierarchies.
+    key_depth: usize,
+    #[clap(long, arg_enum, default_value = "pub-sub")]
+    /// What the peers exchange: `pub-sub` puts messages to subscribers,
+    /// `query` has the subscriber peers query the publisher peers and measures the round-trip time of the replies,
+    /// `ping-pong` has the subscriber peers ping and the publisher peers echo the pings back.
+    test_mode: TestMode,
+    #[clap(long, default_value = "1000")]
+    /// The time a querier (pinger) waits for all the replies (pongs) in query (ping-pong) mode. Unit: milliseconds (ms).
+    reply_timeout: u64,
+    #[clap(long)]
+    /// Run in throughput mode: publishers send for this duration instead of sending num_msgs_per_peer messages,
+    /// and subscribers keep receiving round_timeout ms longer. Unit: milliseconds (ms).
+    throughput_duration: Option<u64>,
+    #[clap(long)]
+    /// Sample the CPU%, RSS, VMS, thread count and context switches of the process at this interval,
+    /// and add the time series to the `exp_sub_*.json` result file (Linux only). Unit: milliseconds (ms).
+    resource_interval: Option<u64>,
+    #[clap(long, parse(from_os_str))]
+    /// The zenoh config file (JSON5 or YAML) every session is built from, e.g. to tune the batch size, the lease or
+    /// the queue sizes. The endpoints and the scouting the experiment needs are added on top of it.
+    zenoh_config: Option<PathBuf>,
+    #[clap(long, parse(from_os_str))]
+    /// A zenoh config file overriding zenoh_config for the publisher sessions: the values it sets replace the ones
+    /// of zenoh_config.
+    pub_zenoh_config: Option<PathBuf>,
+    #[clap(long, parse(from_os_str))]
+    /// A zenoh config file overriding zenoh_config for the subscriber sessions.
+    /// The sessions both publishing and subscribing get the publisher overrides, then the subscriber ones.
+    sub_zenoh_config: Option<PathBuf>,
+    #[clap(skip)]
+    #[serde(skip)]
+    /// The config files loaded by `main`.
+    zenoh_configs: ZenohConfigs,
+}
+
+impl Cli {
+    fn worker_config(&self, start: Instant, process_start: SystemTime) -> WorkerConfig {
+        WorkerConfig {
+            output_dir: self.output_dir.clone(),
+            test_mode: self.test_mode,
+            key_topology: self.key_topology,
+            subscription: self.subscription,
+            topic_num: self.topic_num,
+            key_depth: self.key_depth,
+            total_put_number: self.total_put_number,
+            remote_pub_peers: self.remote_pub_peers,
+            num_msgs_per_peer: self.num_msgs_per_peer,
+            payload_size: self.payload_size,
+            payload_filler: self.payload_filler,
+            round_timeout: self.round_timeout,
+            init_time: self.init_time,
+            multipeer_mode: self.multipeer_mode,
+            locators: self.locators.clone(),
+            pacing: self.pacing,
+            pub_interval: self.pub_interval,
+            pub_interval_freq: self.pub_interval_freq,
+            pub_rate: self.pub_rate,
+            burst_size: self.burst_size,
+            spin_wait: self.spin_wait,
+            publisher_qos: PublisherQos::default(),
+            throughput_duration: self.throughput_duration,
+            reply_timeout: self.reply_timeout,
+            resource_interval: self.resource_interval,
+            start,
+            process_start: Some(process_start),
+            transport_setup: None,
+            peer_endpoints: vec![],
+            session_mode: SessionMode::Peer,
+            router_endpoints: vec![],
+            zenoh_configs: self.zenoh_configs.clone(),
+            sent_msg_nums: SentMsgNums::default(),
+        }
+    }
+
+    fn zenoh_config_files(&self) -> ZenohConfigFiles {
+        ZenohConfigFiles {
+            base: self.zenoh_config.clone(),
+            publisher: self.pub_zenoh_config.clone(),
+            subscriber: self.sub_zenoh_config.clone(),
+            router: None,
+        }
+    }
+}
+
+/// Writes the result of the local subscriber into `exp_sub_*.json`, to be merged with the results
+/// of the other processes, and returns its report.
+async fn sub_result_worker(rx: flume::Receiver<SubReport>, config: WorkerConfig) -> Vec<SubReport> {
+    let resource_sampler = config
+        .resource_interval
+        .map(|interval| ResourceSampler::spawn(config.start, Duration::from_millis(interval)));
+    let mut reports = vec![];
+    while let Ok(report) = rx.recv_async().await {
+        reports.push(report);
+    }
+    let resource_usage = match resource_sampler {
+        Some(resource_sampler) => Some(resource_sampler.stop().await),
+        None => None,
+    };
+    for report in &reports {
+        let pub_peer_ids = (0..config.total_put_number)
+            .chain(report.seq_trackers.keys().copied())
+            .collect::<BTreeSet<_>>();
+        let (mut peer_result, _) = report.peer_result(&pub_peer_ids, &config);
+        peer_result.short_config = Some(config.short_config(report.peer_id));
+        peer_result.resource_usage = resource_usage.clone();
+        peer_result.session_configs = config.zenoh_configs.records();
+        let file_path = config.output_dir.join(format!(
+            "exp_sub_{}_{}.json",
+            report.peer_id,
+            config.exp_name()
+        ));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(file_path)
+            .unwrap();
+        writeln!(
+            &mut file,
+            "{}",
+            serde_json::to_string_pretty(&peer_result).unwrap()
+        )
+        .unwrap();
+    }
+    reports
 }
 
 #[async_std::main]
 async fn main() {
     pretty_env_logger::init();
     // Get & parse arguments
-    let args = Cli::parse();
-    let default_wait_time = (10 * args.total_put_number as u64).max(2000);
+    let mut args = Cli::parse();
+    args.zenoh_configs = ZenohConfigs::load(&args.zenoh_config_files()).unwrap_or_else(|err| {
+        eprintln!("Cannot load the zenoh config: {}", err);
+        std::process::exit(1);
+    });
 
-    async_std::task::sleep(Duration::from_millis(
-        default_wait_time - args.delay_startup,
-    ))
-    .await;
+    if let Some(worker_num) = args.launch {
+        if let Err(err) = launcher::launch_workers(
+            worker_num,
+            args.peer_id.unwrap_or(0),
+            args.delay_startup,
+            &args.output_dir,
+        ) {
+            eprintln!("Launch failed: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(peer_num) = args.coordinator_for {
+        let session = open_session(&args.locators).await;
+        let params =
+            RoundParams::from_config(&args.worker_config(Instant::now(), SystemTime::now()));
+        let start = coordinator_worker(
+            &session,
+            peer_num,
+            params,
+            Duration::from_millis(args.start_delay),
+        )
+        .await
+        .unwrap();
+        println!("Coordinator, start = {:?}", start);
+        if args.collect {
+            let worker_config = args.worker_config(start, SystemTime::now());
+            let collect_timeout = worker_config
+                .timeout()
+                .saturating_duration_since(Instant::now())
+                + Duration::from_millis(args.collect_timeout);
+            let result_path = collector_worker(
+                &session,
+                peer_num,
+                worker_config,
+                args.clone(),
+                collect_timeout,
+            )
+            .await;
+            println!("Collected results written into {:?}", result_path);
+        }
+        session.close().await.unwrap();
+        return;
+    }
+    let peer_id = args.peer_id.unwrap();
+
+    // With a coordinator, the control session stays open during the round to publish the results.
+    let mut control_session = None;
+    let (start, params) = if args.coordinated {
+        let session = open_session(&args.locators).await;
+        let (start, params) = wait_for_start(&session, peer_id).await.unwrap();
+        control_session = Some(session);
+        (start, Some(params))
+    } else {
+        let default_wait_time = (10 * args.total_put_number as u64).max(2000);
+        async_std::task::sleep(Duration::from_millis(
+            default_wait_time.saturating_sub(args.delay_startup),
+        ))
+        .await;
+        (Instant::now(), None)
+    };
 
     // Parameters
-    let start = Instant::now();
-    println!("Peer {}, start = {:?}", args.peer_id, start);
-    let process_start = datetime::Instant::now();
-    let start_until = start + Duration::from_millis(args.init_time);
-    let timeout = start_until + Duration::from_millis(args.round_timeout);
+    println!("Peer {}, start = {:?}", peer_id, start);
+    let process_start = SystemTime::now() + start.saturating_duration_since(Instant::now());
+    let mut worker_config = args.worker_config(start, process_start);
+    if let Some(params) = params {
+        params.apply(&mut worker_config);
+    }
+    if start > Instant::now() {
+        async_std::task::sleep(start - Instant::now()).await;
+    }
+    let (tx, rx) = flume::unbounded::<SubReport>();
 
     // Start workers
-    let pub_sub_fut = pub_and_sub_worker(
-        start_until,
-        timeout,
-        args.peer_id,
-        args.num_msgs_per_peer,
-        get_msg_payload(args.payload_size, args.peer_id),
-        (args.total_put_number + args.remote_pub_peers) * args.num_msgs_per_peer,
-        args.locators.clone(),
-        args.output_dir.clone(),
-        args.total_put_number,
-        args.payload_size,
-        args.clone(),
-        start,
-        process_start,
-    );
-    let _result = futures::join!(pub_sub_fut);
+    let pub_sub_fut = pub_and_sub_worker(peer_id, tx, worker_config.clone());
+    let result_fut = sub_result_worker(rx, worker_config.clone());
+    let (_, reports) = futures::join!(pub_sub_fut, result_fut);
+
+    if let Some(session) = control_session {
+        for report in reports {
+            publish_result(&session, report, &worker_config).await;
+        }
+        session.close().await.unwrap();
+    }
 }
@@ -0,0 +1,143 @@
+mod plots;
+mod results;
+mod svg;
+
+use clap::Parser;
+use results::{load_experiments, Experiment};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+/// Plots the results of the experiments as SVG and gathers them into a static HTML report.
+struct Cli {
+    #[clap(short = 'i', long, parse(from_os_str))]
+    /// The directory of the results, searched recursively for `Exp_*.json` files.
+    input_dir: PathBuf,
+    #[clap(short = 'o', long, parse(from_os_str))]
+    /// The directory to write the SVG plots and report.html into. Defaults to `<input_dir>/report`.
+    output_dir: Option<PathBuf>,
+}
+
+/// The name of the report written into the output directory.
+const REPORT_FILE: &str = "report.html";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// A row per experiment with its parameters and main results.
+fn summary_table(experiments: &[Experiment]) -> String {
+    let mut html = String::from(
+        "<table>\n<tr><th>experiment</th><th>pub</th><th>sub</th><th>msgs/peer</th><th>payload (B)</th>\
+         <th>session mode</th><th>routers</th><th>receive rate</th><th>p50 latency (us)</th><th>p99 latency (us)</th><th>goodput (msgs/s)</th></tr>\n",
+    );
+    for experiment in experiments {
+        let latency = experiment.result.latency.as_ref();
+        let cell = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:?}</td><td>{}</td><td>{:.4}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&experiment.label),
+            experiment.params.put_num,
+            experiment.params.sub_num,
+            experiment.params.num_msgs_per_peer,
+            experiment.params.payload_size,
+            experiment.result.session_mode,
+            experiment.result.router_num,
+            experiment.result.total_receive_rate,
+            cell(latency.map(|latency| latency.p50.to_string())),
+            cell(latency.map(|latency| latency.p99.to_string())),
+            cell(
+                experiment
+                    .result
+                    .throughput
+                    .as_ref()
+                    .map(|throughput| format!("{:.1}", throughput.goodput_msgs_per_sec))
+            ),
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Writes `svg` into `file_name` in `output_dir`, and adds it to the report under `heading`.
+fn add_plot(html: &mut String, output_dir: &Path, file_name: &str, heading: &str, svg: &str) {
+    std::fs::write(output_dir.join(file_name), svg).unwrap();
+    writeln!(html, "<h3>{}</h3>\n{}", escape(heading), svg).unwrap();
+}
+
+fn main() {
+    pretty_env_logger::init();
+    let args = Cli::parse();
+    let output_dir = args
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| args.input_dir.join("report"));
+    let experiments = load_experiments(&args.input_dir).unwrap();
+    println!(
+        "Found {} experiments in {}",
+        experiments.len(),
+        args.input_dir.display()
+    );
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Zenoh performance report</title>\n\
+         <style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+         td, th {{ border: 1px solid #ccc; padding: 2px 8px; text-align: right; }}</style>\n</head>\n<body>\n\
+         <h1>Zenoh performance report</h1>\n<p>{} experiments from {}</p>",
+        experiments.len(),
+        escape(&args.input_dir.display().to_string())
+    )
+    .unwrap();
+    html.push_str("<h2>Summary</h2>\n");
+    html.push_str(&summary_table(&experiments));
+
+    html.push_str("<h2>Plots</h2>\n");
+    add_plot(
+        &mut html,
+        &output_dir,
+        "receive_rate.svg",
+        "Receive rate vs. number of peers",
+        &plots::receive_rate_chart(&experiments).to_svg(),
+    );
+    add_plot(
+        &mut html,
+        &output_dir,
+        "latency_cdf.svg",
+        "Latency CDF",
+        &plots::latency_cdf_chart(&experiments).to_svg(),
+    );
+    add_plot(
+        &mut html,
+        &output_dir,
+        "throughput.svg",
+        "Goodput vs. payload size (throughput mode only)",
+        &plots::throughput_chart(&experiments).to_svg(),
+    );
+
+    html.push_str("<h2>Timelines</h2>\n");
+    for experiment in experiments.iter() {
+        if let Some(timeline) = plots::timeline(experiment) {
+            let file_name = format!("timeline_{}.svg", experiment.label.replace('/', "_"));
+            add_plot(
+                &mut html,
+                &output_dir,
+                &file_name,
+                &experiment.label,
+                &timeline.to_svg(),
+            );
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+
+    let report_path = output_dir.join(REPORT_FILE);
+    std::fs::write(&report_path, html).unwrap();
+    println!("Report written into {}", report_path.display());
+}
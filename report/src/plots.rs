@@ -0,0 +1,149 @@
+use crate::results::Experiment;
+use crate::svg::{Axis, LineChart, Segment, Series, Timeline, TimelineRow};
+use std::collections::BTreeMap;
+
+/// The mean of the values of every x, for the repetitions of the same point.
+fn mean_points(values: BTreeMap<usize, Vec<f64>>) -> Vec<(f64, f64)> {
+    values
+        .into_iter()
+        .map(|(x, values)| (x as f64, values.iter().sum::<f64>() / values.len() as f64))
+        .collect()
+}
+
+/// The receive rate against the number of peers, with a series per payload size.
+pub fn receive_rate_chart(experiments: &[Experiment]) -> LineChart {
+    let mut by_payload = BTreeMap::<usize, BTreeMap<usize, Vec<f64>>>::new();
+    for experiment in experiments {
+        by_payload
+            .entry(experiment.params.payload_size)
+            .or_default()
+            .entry(experiment.params.put_num)
+            .or_default()
+            .push(experiment.result.total_receive_rate);
+    }
+    LineChart {
+        title: "Message receive rate".to_string(),
+        x: Axis::linear("number of peers"),
+        y: Axis::linear("receive rate"),
+        series: by_payload
+            .into_iter()
+            .map(|(payload_size, values)| Series {
+                name: format!("payload {} B", payload_size),
+                points: mean_points(values),
+            })
+            .collect(),
+        markers: true,
+    }
+}
+
+/// The cumulative distribution of the latency of every experiment.
+pub fn latency_cdf_chart(experiments: &[Experiment]) -> LineChart {
+    LineChart {
+        title: "Latency CDF".to_string(),
+        x: Axis::log("latency (us)"),
+        y: Axis::linear("fraction of the messages"),
+        series: experiments
+            .iter()
+            .filter(|experiment| !experiment.result.latency_histogram.is_empty())
+            .map(|experiment| Series {
+                name: experiment.label.clone(),
+                points: experiment
+                    .result
+                    .latency_histogram
+                    .cdf()
+                    .into_iter()
+                    .map(|(nanos, fraction)| (nanos as f64 / 1000.0, fraction))
+                    .collect(),
+            })
+            .collect(),
+        markers: false,
+    }
+}
+
+/// The goodput against the payload size, with a series per number of peers.
+/// Only the experiments run in throughput mode have a goodput.
+pub fn throughput_chart(experiments: &[Experiment]) -> LineChart {
+    let mut by_peers = BTreeMap::<usize, BTreeMap<usize, Vec<f64>>>::new();
+    for experiment in experiments {
+        if let Some(throughput) = &experiment.result.throughput {
+            by_peers
+                .entry(experiment.params.put_num)
+                .or_default()
+                .entry(experiment.params.payload_size)
+                .or_default()
+                .push(throughput.goodput_bytes_per_sec / 1e6);
+        }
+    }
+    LineChart {
+        title: "Goodput".to_string(),
+        x: Axis::log("payload size (bytes)"),
+        y: Axis::linear("goodput (MB/s)"),
+        series: by_peers
+            .into_iter()
+            .map(|(put_num, values)| Series {
+                name: format!("{} peers", put_num),
+                points: mean_points(values),
+            })
+            .collect(),
+        markers: true,
+    }
+}
+
+/// The phases of the timeline, in the order of `Segment::phase`.
+const PHASES: [&str; 5] = [
+    "session open",
+    "declare",
+    "sending",
+    "subscribe",
+    "receiving",
+];
+
+/// When every publisher and subscriber of the experiment went through each phase, from the
+/// `put_*_info_*.json` and `sub_*_info_*.json` files. `None` if there are no such files.
+pub fn timeline(experiment: &Experiment) -> Option<Timeline> {
+    if experiment.pub_statuses.is_empty() && experiment.sub_statuses.is_empty() {
+        return None;
+    }
+    let segment = |start: u128, end: u128, phase: usize| Segment {
+        start: start as f64,
+        end: end as f64,
+        phase,
+    };
+    let session_open = |pub_sub_worker_start: Option<u128>, session_start: Option<u128>| {
+        pub_sub_worker_start
+            .zip(session_start)
+            .map(|(start, end)| segment(start, end, 0))
+    };
+    let pub_rows = experiment.pub_statuses.iter().map(|(peer_id, status)| {
+        let mut segments = session_open(status.pub_sub_worker_start, status.session_start)
+            .into_iter()
+            .collect::<Vec<_>>();
+        segments.push(segment(status.start_pub_worker, status.before_sending, 1));
+        segments.push(segment(status.start_sending, status.after_sending, 2));
+        TimelineRow {
+            label: format!("pub {}", peer_id),
+            segments,
+        }
+    });
+    let sub_rows = experiment.sub_statuses.iter().map(|(peer_id, status)| {
+        let mut segments = session_open(status.pub_sub_worker_start, status.session_start)
+            .into_iter()
+            .collect::<Vec<_>>();
+        segments.push(segment(
+            status.start_sub_worker,
+            status.after_subscribing,
+            3,
+        ));
+        segments.push(segment(status.start_receiving, status.after_receiving, 4));
+        TimelineRow {
+            label: format!("sub {}", peer_id),
+            segments,
+        }
+    });
+    Some(Timeline {
+        title: format!("Timeline of {}", experiment.label),
+        x: Axis::linear("time since the start of the round (ms)"),
+        phases: PHASES.iter().map(|phase| phase.to_string()).collect(),
+        rows: pub_rows.chain(sub_rows).collect(),
+    })
+}
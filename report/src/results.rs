@@ -0,0 +1,153 @@
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use zenoh_perf_core::{PubTimeStatus, SubTimeStatus, TestResult};
+
+/// The parameters of an experiment, as written in the name of its `Exp_*.json` result file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExpParams {
+    pub put_num: usize,
+    pub sub_num: usize,
+    pub num_msgs_per_peer: usize,
+    pub payload_size: usize,
+    pub round_timeout: u64,
+    pub init_time: u64,
+}
+
+impl ExpParams {
+    /// Parses `Exp_{put}-{sub}-{msgs per peer}-{payload size}-{round timeout}-{init time}.json`.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let values = name
+            .strip_prefix("Exp_")?
+            .strip_suffix(".json")?
+            .split('-')
+            .map(|value| value.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match values[..] {
+            [put_num, sub_num, num_msgs_per_peer, payload_size, round_timeout, init_time] => {
+                Some(ExpParams {
+                    put_num: put_num as usize,
+                    sub_num: sub_num as usize,
+                    num_msgs_per_peer: num_msgs_per_peer as usize,
+                    payload_size: payload_size as usize,
+                    round_timeout,
+                    init_time,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The name the workers give to the time status files of the experiment
+    /// (see `WorkerConfig::exp_name`).
+    pub fn status_name(&self) -> String {
+        format!(
+            "{}-{}-{}-{}-{}-{}",
+            self.put_num,
+            self.put_num,
+            self.num_msgs_per_peer,
+            self.payload_size,
+            self.round_timeout,
+            self.init_time
+        )
+    }
+}
+
+/// One experiment of the results directory.
+#[derive(Debug)]
+pub struct Experiment {
+    /// The path of the result file relative to the results directory, without the extension.
+    pub label: String,
+    pub params: ExpParams,
+    /// The configuration is kept as JSON, since every binary writes its own CLI.
+    pub result: TestResult<serde_json::Value>,
+    /// The time statuses of the publishers, by peer id.
+    pub pub_statuses: Vec<(usize, PubTimeStatus)>,
+    /// The time statuses of the subscribers, by peer id.
+    pub sub_statuses: Vec<(usize, SubTimeStatus)>,
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+/// Reads the `{prefix}_{peer id}_info_{status name}.json` files of `dir`, sorted by peer id.
+fn read_statuses<T: DeserializeOwned>(
+    dir: &Path,
+    prefix: &str,
+    status_name: &str,
+) -> Result<Vec<(usize, T)>> {
+    let suffix = format!("_info_{}.json", status_name);
+    let mut statuses = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let peer_id = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(prefix)?.strip_prefix('_'))
+            .and_then(|name| name.strip_suffix(&suffix)?.parse::<usize>().ok());
+        if let Some(peer_id) = peer_id {
+            statuses.push((peer_id, read_json(&path)?));
+        }
+    }
+    statuses.sort_by_key(|(peer_id, _)| *peer_id);
+    Ok(statuses)
+}
+
+impl Experiment {
+    fn load(path: &Path, results_dir: &Path) -> Result<Self> {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        let params = ExpParams::from_file_name(file_name)
+            .ok_or_else(|| anyhow!("unexpected result file name {:?}", file_name))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let status_name = params.status_name();
+        let label = path
+            .strip_prefix(results_dir)
+            .unwrap_or(path)
+            .with_extension("");
+        Ok(Experiment {
+            label: label.display().to_string(),
+            params,
+            result: read_json(path)?,
+            pub_statuses: read_statuses(dir, "put", &status_name)?,
+            sub_statuses: read_statuses(dir, "sub", &status_name)?,
+        })
+    }
+}
+
+fn find_result_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_result_files(&path, files)?;
+        } else if matches!(
+            path.file_name().and_then(|name| name.to_str()),
+            Some(name) if name.starts_with("Exp_") && name.ends_with(".json")
+        ) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Loads the `Exp_*.json` result files found in `results_dir` and its subdirectories (e.g. the
+/// runs of a plan), sorted by parameters. The files that cannot be read are skipped.
+pub fn load_experiments(results_dir: &Path) -> Result<Vec<Experiment>> {
+    let mut files = vec![];
+    find_result_files(results_dir, &mut files)?;
+    let mut experiments = files
+        .iter()
+        .filter_map(|path| match Experiment::load(path, results_dir) {
+            Ok(experiment) => Some(experiment),
+            Err(err) => {
+                eprintln!("Skipping {}: {}", path.display(), err);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    experiments.sort_by(|a, b| (a.params, &a.label).cmp(&(b.params, &b.label)));
+    Ok(experiments)
+}
@@ -0,0 +1,415 @@
+//! Minimal SVG charts, just enough for the plots of the report without a plotting dependency.
+
+use std::fmt::Write;
+
+const WIDTH: f64 = 760.0;
+const HEIGHT: f64 = 440.0;
+const MARGIN_LEFT: f64 = 80.0;
+/// The legend is drawn on the right of the plot area.
+const MARGIN_RIGHT: f64 = 190.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 60.0;
+/// The height of one row of a timeline.
+const ROW_HEIGHT: f64 = 14.0;
+
+/// The matplotlib palette, cycled through by the series.
+const COLORS: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf",
+];
+
+fn color(index: usize) -> &'static str {
+    COLORS[index % COLORS.len()]
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats a tick value without the trailing zeros, e.g. `0.25`, `1000` or `1e6`.
+fn format_value(value: f64) -> String {
+    if value.abs() >= 1e6 {
+        format!("{:e}", value)
+    } else if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        let text = format!("{:.3}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// The step between the ticks of a linear axis, 1, 2 or 5 times a power of 10.
+fn tick_step(span: f64) -> f64 {
+    let raw = span / 5.0;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    match raw / magnitude {
+        ratio if ratio <= 1.0 => magnitude,
+        ratio if ratio <= 2.0 => 2.0 * magnitude,
+        ratio if ratio <= 5.0 => 5.0 * magnitude,
+        _ => 10.0 * magnitude,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Axis {
+    pub label: String,
+    /// Whether the values are shown on a logarithmic scale. Non-positive values are not drawn.
+    pub log: bool,
+}
+
+impl Axis {
+    pub fn linear(label: &str) -> Self {
+        Axis {
+            label: label.to_string(),
+            log: false,
+        }
+    }
+
+    pub fn log(label: &str) -> Self {
+        Axis {
+            label: label.to_string(),
+            log: true,
+        }
+    }
+
+    fn accepts(&self, value: f64) -> bool {
+        value.is_finite() && (!self.log || value > 0.0)
+    }
+
+    /// The position of `value` along the axis, in the units the range and ticks are in.
+    fn position(&self, value: f64) -> f64 {
+        if self.log {
+            value.log10()
+        } else {
+            value
+        }
+    }
+
+    /// The range covering `values`, and the ticks as (position, label). Linear axes always
+    /// start at 0 for positive values, logarithmic ones at a power of 10.
+    fn range_and_ticks<I: Iterator<Item = f64>>(
+        &self,
+        values: I,
+    ) -> ((f64, f64), Vec<(f64, String)>) {
+        let (min, max) = values
+            .map(|value| self.position(value))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        if self.log {
+            let (low, mut high) = if min.is_finite() {
+                (min.floor(), max.ceil())
+            } else {
+                (0.0, 1.0)
+            };
+            if high <= low {
+                high = low + 1.0;
+            }
+            let ticks = (low as i32..=high as i32)
+                .map(|power| (power as f64, format_value(10f64.powi(power))))
+                .collect();
+            ((low, high), ticks)
+        } else {
+            let (low, mut high) = if min.is_finite() {
+                (min.min(0.0), max.max(0.0))
+            } else {
+                (0.0, 1.0)
+            };
+            if high <= low {
+                high = low + 1.0;
+            }
+            let step = tick_step(high - low);
+            let first = (low / step).floor() as i64;
+            let last = (high / step).ceil() as i64;
+            let ticks = (first..=last)
+                .map(|index| (index as f64 * step, format_value(index as f64 * step)))
+                .collect();
+            ((first as f64 * step, last as f64 * step), ticks)
+        }
+    }
+}
+
+/// Maps the positions along the axes to the pixels of the plot area.
+struct Frame {
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    left: f64,
+    right: f64,
+    top: f64,
+    bottom: f64,
+}
+
+impl Frame {
+    fn x(&self, position: f64) -> f64 {
+        let (low, high) = self.x_range;
+        self.left + (position - low) / (high - low) * (self.right - self.left)
+    }
+
+    fn y(&self, position: f64) -> f64 {
+        let (low, high) = self.y_range;
+        self.bottom - (position - low) / (high - low) * (self.bottom - self.top)
+    }
+
+    fn draw_x_axis(&self, svg: &mut String, ticks: &[(f64, String)], label: &str) {
+        for (position, text) in ticks {
+            let x = self.x(*position);
+            writeln!(
+                svg,
+                r##"<line x1="{x:.1}" y1="{}" x2="{x:.1}" y2="{}" stroke="#ddd"/><text x="{x:.1}" y="{}" text-anchor="middle">{}</text>"##,
+                self.top,
+                self.bottom,
+                self.bottom + 16.0,
+                escape(text),
+                x = x
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle" font-weight="bold">{}</text>"#,
+            (self.left + self.right) / 2.0,
+            self.bottom + 40.0,
+            escape(label)
+        )
+        .unwrap();
+    }
+
+    fn draw_y_axis(&self, svg: &mut String, ticks: &[(f64, String)], label: &str) {
+        for (position, text) in ticks {
+            let y = self.y(*position);
+            writeln!(
+                svg,
+                r##"<line x1="{}" y1="{y:.1}" x2="{}" y2="{y:.1}" stroke="#ddd"/><text x="{}" y="{:.1}" text-anchor="end">{}</text>"##,
+                self.left,
+                self.right,
+                self.left - 6.0,
+                y + 4.0,
+                escape(text),
+                y = y
+            )
+            .unwrap();
+        }
+        let x = self.left - 60.0;
+        let y = (self.top + self.bottom) / 2.0;
+        writeln!(
+            svg,
+            r#"<text x="{x}" y="{y}" text-anchor="middle" font-weight="bold" transform="rotate(-90 {x} {y})">{}</text>"#,
+            escape(label),
+            x = x,
+            y = y
+        )
+        .unwrap();
+    }
+
+    fn draw_border(&self, svg: &mut String) {
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            self.left,
+            self.top,
+            self.right - self.left,
+            self.bottom - self.top
+        )
+        .unwrap();
+    }
+
+    /// Lists the `names` with their color on the right of the plot area.
+    fn draw_legend(&self, svg: &mut String, names: &[String]) {
+        for (index, name) in names.iter().enumerate() {
+            let y = self.top + 8.0 + index as f64 * 18.0;
+            writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="12" height="12" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+                self.right + 14.0,
+                y - 10.0,
+                color(index),
+                self.right + 32.0,
+                y,
+                escape(name)
+            )
+            .unwrap();
+        }
+    }
+}
+
+fn open_svg(width: f64, height: f64, title: &str) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+        w = width,
+        h = height
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="white"/><text x="{}" y="24" text-anchor="middle" font-size="15" font-weight="bold">{}</text>"#,
+        (MARGIN_LEFT + width - MARGIN_RIGHT) / 2.0,
+        escape(title)
+    )
+    .unwrap();
+    svg
+}
+
+/// A named list of (x, y) points, drawn in the order of the points.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineChart {
+    pub title: String,
+    pub x: Axis,
+    pub y: Axis,
+    pub series: Vec<Series>,
+    /// Whether a marker is drawn on every point, to tell the measured points from the lines.
+    pub markers: bool,
+}
+
+impl LineChart {
+    pub fn to_svg(&self) -> String {
+        let points = || {
+            self.series
+                .iter()
+                .flat_map(|series| series.points.iter())
+                .filter(|(x, y)| self.x.accepts(*x) && self.y.accepts(*y))
+        };
+        let (x_range, x_ticks) = self.x.range_and_ticks(points().map(|(x, _)| *x));
+        let (y_range, y_ticks) = self.y.range_and_ticks(points().map(|(_, y)| *y));
+        let frame = Frame {
+            x_range,
+            y_range,
+            left: MARGIN_LEFT,
+            right: WIDTH - MARGIN_RIGHT,
+            top: MARGIN_TOP,
+            bottom: HEIGHT - MARGIN_BOTTOM,
+        };
+
+        let mut svg = open_svg(WIDTH, HEIGHT, &self.title);
+        frame.draw_x_axis(&mut svg, &x_ticks, &self.x.label);
+        frame.draw_y_axis(&mut svg, &y_ticks, &self.y.label);
+        for (index, series) in self.series.iter().enumerate() {
+            let pixels = series
+                .points
+                .iter()
+                .filter(|(x, y)| self.x.accepts(*x) && self.y.accepts(*y))
+                .map(|(x, y)| (frame.x(self.x.position(*x)), frame.y(self.y.position(*y))))
+                .collect::<Vec<_>>();
+            let polyline = pixels
+                .iter()
+                .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+                polyline,
+                color(index)
+            )
+            .unwrap();
+            if self.markers {
+                for (x, y) in pixels.iter() {
+                    writeln!(
+                        svg,
+                        r#"<circle cx="{:.1}" cy="{:.1}" r="3" fill="{}"/>"#,
+                        x,
+                        y,
+                        color(index)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        frame.draw_border(&mut svg);
+        let names = self
+            .series
+            .iter()
+            .map(|series| series.name.clone())
+            .collect::<Vec<_>>();
+        frame.draw_legend(&mut svg, &names);
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// One bar of a timeline row, `phase` being the index of its name in `Timeline::phases`.
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub phase: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineRow {
+    pub label: String,
+    pub segments: Vec<Segment>,
+}
+
+/// Horizontal bars showing when each row (peer) went through each phase.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    pub title: String,
+    pub x: Axis,
+    pub phases: Vec<String>,
+    pub rows: Vec<TimelineRow>,
+}
+
+impl Timeline {
+    pub fn to_svg(&self) -> String {
+        let height = MARGIN_TOP + MARGIN_BOTTOM + ROW_HEIGHT * self.rows.len().max(1) as f64;
+        let (x_range, x_ticks) = self.x.range_and_ticks(
+            self.rows
+                .iter()
+                .flat_map(|row| row.segments.iter())
+                .flat_map(|segment| [segment.start, segment.end]),
+        );
+        let frame = Frame {
+            x_range,
+            y_range: (self.rows.len() as f64, 0.0),
+            left: MARGIN_LEFT,
+            right: WIDTH - MARGIN_RIGHT,
+            top: MARGIN_TOP,
+            bottom: height - MARGIN_BOTTOM,
+        };
+
+        let mut svg = open_svg(WIDTH, height, &self.title);
+        frame.draw_x_axis(&mut svg, &x_ticks, &self.x.label);
+        for (index, row) in self.rows.iter().enumerate() {
+            // The y range is reversed so that the first row is at the top.
+            let top = frame.y(index as f64);
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{:.1}" text-anchor="end">{}</text>"#,
+                frame.left - 6.0,
+                top + ROW_HEIGHT - 3.0,
+                escape(&row.label)
+            )
+            .unwrap();
+            for segment in row.segments.iter() {
+                let start = frame.x(self.x.position(segment.start));
+                let end = frame.x(self.x.position(segment.end));
+                writeln!(
+                    svg,
+                    r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+                    start,
+                    top + 2.0,
+                    // Phases shorter than the resolution of the time statuses stay visible.
+                    (end - start).max(1.0),
+                    ROW_HEIGHT - 4.0,
+                    color(segment.phase)
+                )
+                .unwrap();
+            }
+        }
+        frame.draw_border(&mut svg);
+        frame.draw_legend(&mut svg, &self.phases);
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -1,16 +0,0 @@
-pub use anyhow::Result;
-pub use clap::Parser;
-pub use flume;
-pub use futures::{self, prelude::*};
-pub use log::*;
-pub use num_cpus;
-pub use pretty_env_logger;
-pub use rayon::prelude::*;
-pub use serde::{Deserialize, Serialize};
-pub use std::convert::TryInto;
-pub use std::{sync::*, time::*};
-pub use tokio;
-pub use zenoh::config::*;
-pub use zenoh::net::link::EndPoint;
-pub use zenoh::prelude::*;
-pub use zenoh::*;
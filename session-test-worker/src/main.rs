@@ -1,8 +1,10 @@
-mod common;
-
-use crate::common::*;
-use std::io::Write;
+use clap::Parser;
 use std::path::PathBuf;
+use zenoh_perf_core::{
+    common::*, open_session, session_info_worker, wait_for_start, KeyTopology, PacingMode,
+    PayloadFiller, PublisherQos, SentMsgNums, SessionMode, SubscriptionKind, TestMode,
+    WorkerConfig, ZenohConfigFiles, ZenohConfigs,
+};
 
 #[derive(Parser, Debug, Serialize, Deserialize, Clone)]
 #[clap(author, version, about, long_about = None)]
@@ -27,7 +29,7 @@ pub struct Cli {
     #[clap(short = 'm', long, default_value = "1")]
     /// The number of messages each publisher peer will try to send.
     num_msgs_per_peer: usize,
-    #[clap(short = 'n', long, default_value = "8")]
+    #[clap(short = 'n', long, default_value = "40")]
     /// The payload size (bytes) of the message.
     payload_size: usize,
     #[clap(long)]
@@ -54,180 +56,121 @@ pub struct Cli {
     /// Note that the num_msgs_per_peer needs to be the same on both remote and local machines
     remote_pub_peers: usize,
     #[clap(short = 'd', long, default_value = "0")]
+    /// Shortens the fixed startup wait of this process. Unit: milliseconds (ms).
+    /// Ignored with `--coordinated`.
     delay_startup: u64,
-    #[clap(short = 's', long, default_value = "0.2")]
+    #[clap(long)]
+    /// Wait for the start of the round from a coordinator (`pub-sub-worker --coordinator-for`) instead of a fixed startup wait.
+    coordinated: bool,
+    #[clap(short = 's', long, default_value = "200")]
+    /// The time the session scouts for peers before it is opened. Unit: milliseconds (ms).
     scout_delay: u64,
+    #[clap(long)]
+    /// Sample the CPU%, RSS, VMS, thread count and context switches of the process at this interval,
+    /// and add the time series to the `Session_*_info_*.json` result file (Linux only). Unit: milliseconds (ms).
+    resource_interval: Option<u64>,
+    #[clap(long, parse(from_os_str))]
+    /// The zenoh config file (JSON5 or YAML) every session is built from, e.g. to tune the batch size, the lease or
+    /// the queue sizes. The endpoints and the scouting the experiment needs are added on top of it.
+    zenoh_config: Option<PathBuf>,
+    #[clap(long, parse(from_os_str))]
+    /// A zenoh config file overriding zenoh_config for the publisher sessions: the values it sets replace the ones
+    /// of zenoh_config.
+    pub_zenoh_config: Option<PathBuf>,
+    #[clap(long, parse(from_os_str))]
+    /// A zenoh config file overriding zenoh_config for the subscriber sessions.
+    /// The sessions both publishing and subscribing get the publisher overrides, then the subscriber ones.
+    sub_zenoh_config: Option<PathBuf>,
+    #[clap(skip)]
+    #[serde(skip)]
+    /// The config files loaded by `main`.
+    zenoh_configs: ZenohConfigs,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct TimeStatus {
-    pub session_start: Option<u128>,
-    pub pub_sub_worker_start: Option<u128>,
-    pub list_start_timestamp: Vec<u128>,
-    pub list_sess_start_timestamp: Vec<u128>,
-    pub list_timestamp_peer_num: Vec<usize>,
-    pub list_timestamp_res: Vec<Vec<String>>,
-    pub session_id: Option<String>,
-}
-
-pub fn get_msg_payload(args_payload_size: usize, peer_id: usize) -> String {
-    let mut msg_payload;
-    if args_payload_size == 8 {
-        msg_payload = format!("{:08}", peer_id);
-        let payload_size = std::mem::size_of_val(msg_payload.as_bytes());
-        assert!(payload_size == args_payload_size);
-    } else if args_payload_size < 8 {
-        warn!("Payload size cannot be less than 8 bytes, using 8 bytes for current test.");
-        msg_payload = format!("{:08}", peer_id);
-        let payload_size = std::mem::size_of_val(msg_payload.as_bytes());
-        assert!(payload_size == 8);
-    } else {
-        msg_payload = format!("{:08}", peer_id);
-        let additional_size = args_payload_size - 8;
-        let mut postpend_string = String::from(".");
-        for _ in 1..additional_size {
-            postpend_string.push_str(".");
+impl Cli {
+    fn worker_config(&self, start: Instant) -> WorkerConfig {
+        WorkerConfig {
+            output_dir: self.output_dir.clone(),
+            test_mode: TestMode::PubSub,
+            key_topology: KeyTopology::Shared,
+            subscription: SubscriptionKind::Wildcard,
+            topic_num: 1,
+            key_depth: 0,
+            total_put_number: self.num_put_peer,
+            remote_pub_peers: self.remote_pub_peers,
+            num_msgs_per_peer: self.num_msgs_per_peer,
+            payload_size: self.payload_size,
+            payload_filler: PayloadFiller::Dot,
+            round_timeout: self.round_timeout,
+            init_time: self.init_time,
+            multipeer_mode: self.multipeer_mode,
+            locators: self.locators.clone(),
+            pub_interval: 0,
+            pub_interval_freq: 0,
+            throughput_duration: None,
+            reply_timeout: 0,
+            resource_interval: self.resource_interval,
+            pacing: PacingMode::Interval,
+            pub_rate: None,
+            burst_size: 1,
+            spin_wait: false,
+            publisher_qos: PublisherQos::default(),
+            start,
+            process_start: None,
+            transport_setup: None,
+            peer_endpoints: vec![],
+            session_mode: SessionMode::Peer,
+            router_endpoints: vec![],
+            zenoh_configs: self.zenoh_configs.clone(),
+            sent_msg_nums: SentMsgNums::default(),
         }
-        msg_payload.push_str(&postpend_string);
-        let payload_size = std::mem::size_of_val(msg_payload.as_bytes());
-        assert!(payload_size == args_payload_size);
     }
-    msg_payload
-}
-
-pub async fn pub_and_sub_worker(
-    _start_until: Instant,
-    timeout: Instant,
-    peer_id: usize,
-    num_msgs_per_peer: usize,
-    _msg_payload: String,
-    _total_msg_num: usize,
-    locators: Vec<Locator>,
-    _output_dir: PathBuf,
-    total_put_number: usize,
-    payload_size: usize,
-    args: Cli,
-    start: Instant,
-) -> anyhow::Result<()> {
-    let pub_sub_worker_start = Some(Instant::now());
-    let mut config = config::default();
-
-    let endpoints = locators
-        .into_iter()
-        .map(|locator| EndPoint::from(locator))
-        .collect::<Vec<_>>();
-    let connect_config = ConnectConfig { endpoints };
-    config.set_connect(connect_config).unwrap();
 
-    config.scouting.set_delay(Some(args.scout_delay)).unwrap();
-    let zenoh = Arc::new(zenoh::open(config).await.unwrap());
-    let session_start_time = Some(Instant::now());
-    let mut list_start_timestamp: Vec<u128> = vec![];
-    let mut list_sess_start_timestamp: Vec<u128> = vec![];
-    let mut list_timestamp_peer_num: Vec<usize> = vec![];
-    let mut list_timestamp_res: Vec<Vec<String>> = vec![];
-    let mut session_id: Option<String> = None;
-
-    while Instant::now() < timeout || session_id.is_none() {
-        // Todo: Sleep and get duration & peer info
-        let _sleep_end_time = Instant::now();
-        let session_info = zenoh.info().await;
-        let after_session_info = Instant::now();
-        if session_id.is_none() {
-            session_id = Some(session_info.get(&0).unwrap().clone());
+    fn zenoh_config_files(&self) -> ZenohConfigFiles {
+        ZenohConfigFiles {
+            base: self.zenoh_config.clone(),
+            publisher: self.pub_zenoh_config.clone(),
+            subscriber: self.sub_zenoh_config.clone(),
+            router: None,
         }
-        list_start_timestamp.push((after_session_info - start).as_millis());
-        list_sess_start_timestamp
-            .push((after_session_info - session_start_time.unwrap()).as_millis());
-        let curr_peer_num = session_info
-            .get(&1)
-            .unwrap()
-            .split(",")
-            .map(|s| s.to_string())
-            .filter(|s| s != "")
-            .collect::<Vec<String>>();
-        list_timestamp_peer_num.push(curr_peer_num.len());
-        list_timestamp_res.push(curr_peer_num);
-        // println!(
-        //     "Process time = {} ms",
-        //     (Instant::now() - after_session_info).as_millis()
-        // );
-
-        async_std::task::sleep(Duration::from_millis(100)).await;
     }
-
-    let zenoh = Arc::try_unwrap(zenoh).map_err(|_| ()).unwrap();
-    zenoh.close().await.unwrap();
-
-    let file_path = args.output_dir.join(format!(
-        "Session_{}_info_{}-{}-{}-{}-{}-{}.json",
-        peer_id,
-        total_put_number,
-        total_put_number,
-        num_msgs_per_peer,
-        payload_size,
-        args.round_timeout,
-        args.init_time
-    ));
-    let session_start = Some((session_start_time.unwrap() - start).as_millis());
-    let pus_sub_work_start_dur = Some((pub_sub_worker_start.unwrap() - start).as_millis());
-    let test_result = TimeStatus {
-        session_start,
-        pub_sub_worker_start: pus_sub_work_start_dur,
-        list_start_timestamp,
-        list_sess_start_timestamp,
-        list_timestamp_peer_num,
-        list_timestamp_res,
-        session_id,
-    };
-
-    let mut file = std::fs::File::create(file_path).unwrap();
-    writeln!(
-        &mut file,
-        "{}",
-        serde_json::to_string_pretty(&test_result).unwrap()
-    )
-    .unwrap();
-
-    Ok(())
 }
 
 #[async_std::main]
 async fn main() {
     pretty_env_logger::init();
     // Get & parse arguments
-    let args = Cli::parse();
-    let default_wait_time = (10 * args.num_put_peer as u64).max(2000);
-
-    async_std::task::sleep(Duration::from_millis(
-        default_wait_time - args.delay_startup,
-    ))
-    .await;
+    let mut args = Cli::parse();
+    args.zenoh_configs = ZenohConfigs::load(&args.zenoh_config_files()).unwrap_or_else(|err| {
+        eprintln!("Cannot load the zenoh config: {}", err);
+        std::process::exit(1);
+    });
+
+    let (start, params) = if args.coordinated {
+        let session = open_session(&args.locators).await;
+        let (start, params) = wait_for_start(&session, args.peer_id).await.unwrap();
+        session.close().await.unwrap();
+        (start, Some(params))
+    } else {
+        let default_wait_time = (10 * args.num_put_peer as u64).max(2000);
+        async_std::task::sleep(Duration::from_millis(
+            default_wait_time.saturating_sub(args.delay_startup),
+        ))
+        .await;
+        (Instant::now(), None)
+    };
 
     // Parameters
-    let start = Instant::now();
     println!("Peer {}, start = {:?}", args.peer_id, start);
-    let start_until = start + Duration::from_millis(args.init_time);
-    let timeout = start_until + Duration::from_millis(args.round_timeout);
-    let total_put_number = args.num_put_peer;
-    let total_cpu_num = num_cpus::get();
-    let available_cpu_num = (total_cpu_num - 2).max(1);
-    let _per_peer_num = total_put_number / available_cpu_num;
+    let mut worker_config = args.worker_config(start);
+    if let Some(params) = params {
+        params.apply(&mut worker_config);
+    }
+    if start > Instant::now() {
+        async_std::task::sleep(start - Instant::now()).await;
+    }
 
-    let pub_sub_futs = pub_and_sub_worker(
-        start_until,
-        timeout,
-        args.peer_id,
-        args.num_msgs_per_peer,
-        get_msg_payload(args.payload_size, args.peer_id),
-        (total_put_number + args.remote_pub_peers) * args.num_msgs_per_peer,
-        args.locators.clone(),
-        args.output_dir.clone(),
-        total_put_number,
-        args.payload_size,
-        args,
-        start,
-    );
+    let pub_sub_futs = session_info_worker(args.peer_id, worker_config, Some(args.scout_delay));
 
     let _result = futures::join!(pub_sub_futs);
 }
@@ -1,11 +1,16 @@
-mod common;
-mod utils;
-mod workers;
+mod plan;
+mod priority;
+mod transports;
+
 use clap::Parser;
-use common::*;
 use std::path::PathBuf;
-use utils::*;
-use workers::*;
+use zenoh_perf_core::{
+    common::*, demonstration_worker, pub_and_sub_worker, publish_worker, subscribe_worker,
+    CongestionMode, KeyTopology, PacingMode, PayloadFiller, PriorityClass, PublisherGroup,
+    PublisherQos, RouterChain, SentMsgNums, SessionMode, SessionRole, SubReport, SubscriptionKind,
+    TestMode, Topology, TopologyKind, Transport, TransportSetup, WorkerConfig, ZenohConfigFiles,
+    ZenohConfigs,
+};
 
 #[derive(Parser, Debug, Serialize, Deserialize, Clone)]
 #[clap(author, version, about, long_about = None)]
@@ -30,9 +35,13 @@ pub struct Cli {
     #[clap(short = 'm', long, default_value = "1")]
     /// The number of messages each publisher peer will try to send.
     num_msgs_per_peer: usize,
-    #[clap(short = 'n', long, default_value = "8")]
+    #[clap(short = 'n', long, default_value = "40")]
     /// The payload size (bytes) of the message.
+    /// The first 40 bytes carry the binary header (publisher id, sequence number, send timestamp, etc.).
     payload_size: usize,
+    #[clap(long, arg_enum, default_value = "dot")]
+    /// The bytes used to fill the payload after the header.
+    payload_filler: PayloadFiller,
     #[clap(long)]
     /// The number of tasks to spawn for dealing with futures related to publisher peers.
     pub_cpu_num: Option<usize>,
@@ -56,6 +65,11 @@ pub struct Cli {
     /// Used to notify subscribers to receive messages from remote peers.
     /// Note that the num_msgs_per_peer needs to be the same on both remote and local machines
     remote_pub_peers: usize,
+    #[clap(long, arg_enum, default_value = "interval")]
+    /// How publishers space out their messages.
+    /// `interval` sleeps pub_interval after every pub_interval_freq messages,
+    /// the other modes send at pub_rate (constant, token-bucket with bursts of burst_size, or poisson arrivals).
+    pacing: PacingMode,
     #[clap(long, default_value = "1")]
     /// The interval between the messages published by publisher. (Unit: ms)
     pub pub_interval: u64,
@@ -63,28 +77,247 @@ pub struct Cli {
     /// The frequency to add the pub_interval. (Unit: messages/times)
     /// If not specified, it is turned off. (Not pub_interval will be used)
     pub pub_interval_freq: usize,
+    #[clap(long)]
+    /// The rate each publisher sends at with the constant, token-bucket and poisson pacings. Unit: messages/s.
+    pub_rate: Option<f64>,
+    #[clap(long, default_value = "1")]
+    /// The maximum number of messages sent back to back with the token-bucket pacing.
+    burst_size: usize,
+    #[clap(long)]
+    /// Busy-wait the last 2 ms before every deadline of the constant, token-bucket and poisson pacings,
+    /// for a sub-millisecond accuracy at the cost of a busy core per publisher.
+    spin_wait: bool,
+    #[clap(long, arg_enum, default_value = "shared")]
+    /// How the keys of the publishers are laid out: `shared` puts on a single key (`/demo/example/hello`),
+    /// `per-publisher` puts on one key per publisher (`/bench/<peer id>`),
+    /// `topics` spreads the publishers and subscribers over topic_num topics (`/bench/topic/<topic>`).
+    key_topology: KeyTopology,
+    #[clap(long, arg_enum, default_value = "wildcard")]
+    /// Whether subscribers declare a single wildcard subscription or one subscription for each exact key.
+    subscription: SubscriptionKind,
+    #[clap(long, default_value = "1")]
+    /// The number of topics with the `topics` key topology.
+    topic_num: usize,
+    #[clap(long, default_value = "0")]
+    /// The number of intermediate levels (`/l0/l1/...`) inserted in every key, to test deep hierarchies.
+    key_depth: usize,
+    #[clap(long, arg_enum, default_value = "pub-sub")]
+    /// What the peers exchange: `pub-sub` puts messages to subscribers,
+    /// `query` has the subscriber peers query the publisher peers and measures the round-trip time of the replies,
+    /// `ping-pong` has the subscriber peers ping and the publisher peers echo the pings back.
+    test_mode: TestMode,
+    #[clap(long, default_value = "1000")]
+    /// The time a querier (pinger) waits for all the replies (pongs) in query (ping-pong) mode. Unit: milliseconds (ms).
+    reply_timeout: u64,
+    #[clap(long)]
+    /// Run in throughput mode: publishers send for this duration instead of sending num_msgs_per_peer messages,
+    /// and subscribers keep receiving round_timeout ms longer. Unit: milliseconds (ms).
+    throughput_duration: Option<u64>,
+    #[clap(long)]
+    /// Sample the CPU%, RSS, VMS, thread count and context switches of the process at this interval,
+    /// and add the time series to the result file (Linux only). Unit: milliseconds (ms).
+    resource_interval: Option<u64>,
+    #[clap(long, parse(from_os_str))]
+    /// Run the experiments described in a plan file (TOML) instead of a single experiment.
+    /// The other options are used as the defaults of every run in the plan.
+    plan: Option<PathBuf>,
+    #[clap(long, arg_enum, value_delimiter = ',', conflicts_with = "plan")]
+    /// Run the experiment once over each of these transports (e.g. tcp,udp,unixsock-stream,tls) on the loopback,
+    /// and compare them in transport_matrix.json. The ports, socket paths and TLS certificates are allocated at runtime,
+    /// and the runs use separate publisher and subscriber sessions connecting to a listening session.
+    transports: Vec<Transport>,
+    #[clap(skip)]
+    /// The transport of the current run of the transport matrix.
+    transport_setup: Option<TransportSetup>,
+    #[clap(long, arg_enum, conflicts_with_all = &["pub-sub-separate", "transports"])]
+    /// Link the peers in this graph instead of connecting them all to the locators: every peer listens on its own
+    /// loopback port and connects to its neighbours (`star` on peer 0, `chain`, `ring`, `full-mesh`,
+    /// `tree` with topology_degree children per node, `random-regular` with topology_degree neighbours per peer).
+    peer_topology: Option<TopologyKind>,
+    #[clap(long, default_value = "2")]
+    /// The number of children of a node with the `tree` topology, of neighbours of a peer with `random-regular`.
+    topology_degree: usize,
+    #[clap(long, default_value = "0")]
+    /// The seed of the `random-regular` topology.
+    topology_seed: u64,
+    #[clap(long, arg_enum, default_value = "peer")]
+    /// The mode the sessions of the publishers and subscribers are opened in (`peer` or `client`).
+    session_mode: SessionMode,
+    #[clap(long, default_value = "0", conflicts_with_all = &["transports", "peer-topology"])]
+    /// Start this many zenoh routers in the process, each connected to the previous one, and connect the sessions
+    /// to them: publishers to the first router and subscribers to the last one, or the pub/sub peers spread over the
+    /// routers. Separate publishers and subscribers always get their own sessions (as with multipeer_mode).
+    routers: usize,
+    #[clap(skip)]
+    /// The endpoints of the routers started for the current run.
+    router_endpoints: Vec<String>,
+    #[clap(long, parse(from_os_str))]
+    /// The zenoh config file (JSON5 or YAML) every session is built from, e.g. to tune the batch size, the lease or
+    /// the queue sizes. The endpoints, the mode and the scouting the experiment needs are added on top of it.
+    zenoh_config: Option<PathBuf>,
+    #[clap(long, parse(from_os_str))]
+    /// A zenoh config file overriding zenoh_config for the publisher sessions: the values it sets replace the ones
+    /// of zenoh_config.
+    pub_zenoh_config: Option<PathBuf>,
+    #[clap(long, parse(from_os_str))]
+    /// A zenoh config file overriding zenoh_config for the subscriber sessions.
+    /// The sessions both publishing and subscribing get the publisher overrides, then the subscriber ones.
+    sub_zenoh_config: Option<PathBuf>,
+    #[clap(long, parse(from_os_str))]
+    /// A zenoh config file overriding zenoh_config for the in-process routers.
+    router_zenoh_config: Option<PathBuf>,
+    #[clap(skip)]
+    #[serde(skip)]
+    /// The config files loaded for the current run, recording the config of every session.
+    zenoh_configs: ZenohConfigs,
+    #[clap(long, arg_enum, default_value = "data")]
+    /// The priority of the messages of the publishers outside of the publisher groups.
+    priority: PriorityClass,
+    #[clap(long, arg_enum, default_value = "drop")]
+    /// What the publishers outside of the publisher groups do when the queues are full: `block` waits, `drop` drops the message.
+    congestion_control: CongestionMode,
+    #[clap(long = "pub-group", multiple_occurrences = true)]
+    /// A group of publishers with their own QoS, as name:count:priority:congestion[:rate] (e.g. alarm:1:real-time:block:100).
+    /// The publishers are assigned to the groups in the order of their peer ids, and a group with a rate sends at that constant rate.
+    /// The result file then has the latency and the lost messages of every group in class_stats.
+    pub_groups: Vec<PublisherGroup>,
+    #[clap(long, conflicts_with_all = &["plan", "transports", "peer-topology", "pub-groups"])]
+    /// Measure the latency of a high-priority flow while low-priority publishers flood the same session:
+    /// publisher 0 sends at priority_rate with the real-time priority, the others flat out with the data-low priority.
+    /// The publishers send for throughput_duration (5000 ms by default).
+    priority_flood: bool,
+    #[clap(long, default_value = "100")]
+    /// The rate of the high-priority flow of the priority scenario. Unit: messages/s.
+    priority_rate: f64,
+}
+
+impl Cli {
+    fn worker_config(&self, start: Instant) -> WorkerConfig {
+        WorkerConfig {
+            output_dir: self.output_dir.clone(),
+            test_mode: self.test_mode,
+            key_topology: self.key_topology,
+            subscription: self.subscription,
+            topic_num: self.topic_num,
+            key_depth: self.key_depth,
+            total_put_number: self.num_put_peer,
+            remote_pub_peers: self.remote_pub_peers,
+            num_msgs_per_peer: self.num_msgs_per_peer,
+            payload_size: self.payload_size,
+            payload_filler: self.payload_filler,
+            round_timeout: self.round_timeout,
+            init_time: self.init_time,
+            multipeer_mode: self.multipeer_mode,
+            locators: self.locators.clone(),
+            pacing: self.pacing,
+            pub_interval: self.pub_interval,
+            pub_interval_freq: self.pub_interval_freq,
+            pub_rate: self.pub_rate,
+            burst_size: self.burst_size,
+            spin_wait: self.spin_wait,
+            publisher_qos: PublisherQos {
+                groups: self.pub_groups.clone(),
+                priority: self.priority,
+                congestion_control: self.congestion_control,
+            },
+            throughput_duration: self.throughput_duration,
+            reply_timeout: self.reply_timeout,
+            resource_interval: self.resource_interval,
+            start,
+            process_start: None,
+            transport_setup: self.transport_setup.clone(),
+            peer_endpoints: vec![],
+            session_mode: self.session_mode,
+            router_endpoints: self.router_endpoints.clone(),
+            zenoh_configs: self.zenoh_configs.clone(),
+            sent_msg_nums: SentMsgNums::default(),
+        }
+    }
+
+    fn zenoh_config_files(&self) -> ZenohConfigFiles {
+        ZenohConfigFiles {
+            base: self.zenoh_config.clone(),
+            publisher: self.pub_zenoh_config.clone(),
+            subscriber: self.sub_zenoh_config.clone(),
+            router: self.router_zenoh_config.clone(),
+        }
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        self.peer_topology.map(|kind| Topology {
+            kind,
+            degree: self.topology_degree,
+            seed: self.topology_seed,
+        })
+    }
 }
+
 #[async_std::main]
 async fn main() {
     pretty_env_logger::init();
     let args = Cli::parse();
     dbg!(&args);
     println!("# of CPU cores = {}", num_cpus::get());
-    if args.pub_sub_separate {
-        test_worker_1(args).await;
+    if let Some(plan_path) = args.plan.clone() {
+        plan::run_plan(&plan_path, args).await.unwrap();
+    } else if !args.transports.is_empty() {
+        transports::run_transport_matrix(args).await.unwrap();
+    } else if args.priority_flood {
+        let result_path = priority::run_priority_flood(args).await.unwrap();
+        println!("Result written into {:?}", result_path);
+    } else {
+        run_experiment(args).await;
+    }
+}
+
+/// Runs a single experiment and returns the path of its `Exp_*.json` result file.
+async fn run_experiment(mut args: Cli) -> PathBuf {
+    // Loaded for every run, so that the result of a run only records the sessions of the run.
+    args.zenoh_configs = ZenohConfigs::load(&args.zenoh_config_files()).unwrap();
+    let routers = if args.routers > 0 {
+        let routers = RouterChain::start(args.routers, &args.zenoh_configs)
+            .await
+            .unwrap();
+        println!(
+            "Started {} routers: {}",
+            args.routers,
+            routers.endpoints.join(" -> ")
+        );
+        args.router_endpoints = routers.endpoints.clone();
+        // A session shared by the publishers and subscribers would not go through the routers.
+        args.multipeer_mode = true;
+        Some(routers)
     } else {
-        test_pub_and_sub_worker(args).await;
+        None
+    };
+    let result_path = if args.pub_sub_separate {
+        if args.peer_topology.is_some() {
+            warn!("The peer topology is ignored with separate publishers and subscribers.");
+        }
+        test_worker_1(args).await
+    } else {
+        test_pub_and_sub_worker(args).await
+    };
+    if let Some(routers) = routers {
+        routers.close().await;
     }
+    result_path
 }
 
-async fn test_pub_and_sub_worker(args: Cli) {
-    let (tx, rx) = flume::unbounded::<(usize, Vec<Sample>)>();
+async fn test_pub_and_sub_worker(args: Cli) -> PathBuf {
+    let (tx, rx) = flume::unbounded::<SubReport>();
 
     let start = Instant::now();
-    let start_until = start + Duration::from_millis(args.init_time);
-    let timeout = start_until + Duration::from_millis(args.round_timeout);
+    let mut worker_config = args.worker_config(start);
     let total_sub_number = args.num_put_peer;
     let total_put_number = args.num_put_peer;
+    if let Some(topology) = args.topology() {
+        worker_config.peer_endpoints = topology.peer_endpoints(total_put_number).unwrap();
+        println!(
+            "Linking the {} peers in a {:?} topology",
+            total_put_number, topology.kind
+        );
+    }
     let total_cpu_num = num_cpus::get();
     let available_cpu_num = (total_cpu_num - 2).max(1);
     let per_peer_num = total_put_number / available_cpu_num;
@@ -94,38 +327,21 @@ async fn test_pub_and_sub_worker(args: Cli) {
             .into_par_iter()
             .map(|peer_index| {
                 async_std::task::spawn(pub_and_sub_worker(
-                    start_until,
-                    timeout,
                     peer_index,
-                    args.num_msgs_per_peer,
-                    get_msg_payload(args.payload_size, peer_index),
                     tx.clone(),
-                    (total_put_number + args.remote_pub_peers) * args.num_msgs_per_peer,
-                    args.locators.clone(),
-                    args.output_dir.clone(),
-                    total_put_number,
-                    args.payload_size,
-                    args.clone(),
-                    start,
+                    worker_config.clone(),
                 ))
             })
             .collect::<Vec<_>>();
         let all_fut = futures::future::join_all(pub_sub_futs);
 
-        let demo_fut = demonstration_worker(
-            rx,
-            total_put_number,
-            total_sub_number,
-            args.num_msgs_per_peer,
-            args.remote_pub_peers,
-            args.payload_size,
-            args.round_timeout,
-            args.clone(),
-        );
+        let demo_fut =
+            demonstration_worker(rx, total_sub_number, worker_config.clone(), args.clone());
 
         drop(tx);
 
-        futures::join!(all_fut, demo_fut);
+        let (_, result_path) = futures::join!(all_fut, demo_fut);
+        result_path
     } else {
         let mut pub_sub_futs = (0..available_cpu_num)
             .into_par_iter()
@@ -134,19 +350,9 @@ async fn test_pub_and_sub_worker(args: Cli) {
                     .into_par_iter()
                     .map(|peer_index| {
                         pub_and_sub_worker(
-                            start_until,
-                            timeout,
                             peer_index + core_idx * per_peer_num,
-                            args.num_msgs_per_peer,
-                            get_msg_payload(args.payload_size, peer_index),
                             tx.clone(),
-                            (total_put_number + args.remote_pub_peers) * args.num_msgs_per_peer,
-                            args.locators.clone(),
-                            args.output_dir.clone(),
-                            total_put_number,
-                            args.payload_size,
-                            args.clone(),
-                            start,
+                            worker_config.clone(),
                         )
                     })
                     .collect::<Vec<_>>();
@@ -157,23 +363,7 @@ async fn test_pub_and_sub_worker(args: Cli) {
         let remaining_pub_sub = total_put_number % available_cpu_num;
         let remaining_pub_sub_fut = (total_put_number - remaining_pub_sub..total_put_number)
             .into_par_iter()
-            .map(|peer_index| {
-                pub_and_sub_worker(
-                    start_until,
-                    timeout,
-                    peer_index,
-                    args.num_msgs_per_peer,
-                    get_msg_payload(args.payload_size, peer_index),
-                    tx.clone(),
-                    (total_put_number + args.remote_pub_peers) * args.num_msgs_per_peer,
-                    args.locators.clone(),
-                    args.output_dir.clone(),
-                    total_put_number,
-                    args.payload_size,
-                    args.clone(),
-                    start,
-                )
-            })
+            .map(|peer_index| pub_and_sub_worker(peer_index, tx.clone(), worker_config.clone()))
             .collect::<Vec<_>>();
 
         let remaining_pub_sub_fut =
@@ -182,41 +372,28 @@ async fn test_pub_and_sub_worker(args: Cli) {
 
         let all_fut = futures::future::join_all(pub_sub_futs);
 
-        let demo_fut = demonstration_worker(
-            rx,
-            total_put_number,
-            total_sub_number,
-            args.num_msgs_per_peer,
-            args.remote_pub_peers,
-            args.payload_size,
-            args.round_timeout,
-            args.clone(),
-        );
+        let demo_fut =
+            demonstration_worker(rx, total_sub_number, worker_config.clone(), args.clone());
 
         drop(tx);
 
-        futures::join!(all_fut, demo_fut);
+        let (_, result_path) = futures::join!(all_fut, demo_fut);
+        result_path
     }
 }
 
-async fn test_worker_1(args: Cli) {
-    let (tx, rx) = flume::unbounded::<(usize, Vec<Sample>)>();
-    let mut config = config::default();
-
-    let endpoints = args
-        .locators
-        .clone()
-        .into_iter()
-        .map(|locator| EndPoint::from(locator))
-        .collect::<Vec<_>>();
-    let listerner_config = ListenConfig { endpoints };
-    config.set_listen(listerner_config).unwrap();
+async fn test_worker_1(args: Cli) -> PathBuf {
+    let (tx, rx) = flume::unbounded::<SubReport>();
+    let mut worker_config = args.worker_config(Instant::now());
 
-    let zenoh = Arc::new(zenoh::open(config).await.unwrap());
+    let zenoh = Arc::new(
+        worker_config
+            .open_worker_session(SessionRole::Listener)
+            .await,
+    );
 
     let start = Instant::now();
-    let start_until = start + Duration::from_millis(args.init_time);
-    let timeout = start_until + Duration::from_millis(args.round_timeout);
+    worker_config.start = start;
     let total_sub_number = args.num_sub_peer;
     let total_put_number = args.num_put_peer;
     let total_cpu_num = num_cpus::get();
@@ -227,17 +404,6 @@ async fn test_worker_1(args: Cli) {
         }
     }
 
-    // Old subscriber
-    // let sub_handle_vec = (0..total_sub_number)
-    //     .into_par_iter()
-    //     .map(|peer_id: usize| {
-    //         let sub_handle =
-    //             subscribe_worker(zenoh.clone(), start_until, timeout, peer_id, tx.clone());
-    //         sub_handle
-    //     })
-    //     .collect::<Vec<_>>();
-
-    // new subscriber
     let sub_cpu_num;
     if let Some(args_sub_cpu_num) = args.sub_cpu_num {
         sub_cpu_num = args_sub_cpu_num;
@@ -258,15 +424,9 @@ async fn test_worker_1(args: Cli) {
                 .map(|peer_index| {
                     subscribe_worker(
                         zenoh.clone(),
-                        start_until,
-                        timeout,
                         peer_index + core_idx * sub_per_peer_num,
                         tx.clone(),
-                        args.multipeer_mode,
-                        (total_put_number + args.remote_pub_peers) * args.num_msgs_per_peer,
-                        args.locators.clone(),
-                        args.clone(),
-                        start,
+                        worker_config.clone(),
                         None,
                         None,
                     )
@@ -281,15 +441,9 @@ async fn test_worker_1(args: Cli) {
         .map(|peer_index| {
             subscribe_worker(
                 zenoh.clone(),
-                start_until,
-                timeout,
                 peer_index,
                 tx.clone(),
-                args.multipeer_mode,
-                (total_put_number + args.remote_pub_peers) * args.num_msgs_per_peer,
-                args.locators.clone(),
-                args.clone(),
-                start,
+                worker_config.clone(),
                 None,
                 None,
             )
@@ -299,20 +453,6 @@ async fn test_worker_1(args: Cli) {
     let remain_sub_futs = async_std::task::spawn(futures::future::join_all(remaining_sub_fut));
     sub_futs.push(remain_sub_futs);
 
-    // Old publisher futures
-    // let pub_futures = (0..total_put_number).map(|peer_index| {
-    //     publish_worker(
-    //         zenoh.clone(),
-    //         start_until,
-    //         timeout,
-    //         peer_index,
-    //         args.num_msgs_per_peer,
-    //         &msg_payload,
-    //     )
-    // });
-    // futures::future::try_join_all(pub_futures).await.unwrap();
-
-    // new publisher futures
     let pub_cpu_num;
     if let Some(args_pub_cpu_num) = args.pub_cpu_num {
         pub_cpu_num = args_pub_cpu_num;
@@ -333,18 +473,8 @@ async fn test_worker_1(args: Cli) {
                 .map(|peer_index| {
                     publish_worker(
                         zenoh.clone(),
-                        start_until,
-                        timeout,
                         peer_index + core_idx * pub_per_peer_num,
-                        args.num_msgs_per_peer,
-                        get_msg_payload(args.payload_size, peer_index),
-                        args.multipeer_mode,
-                        args.locators.clone(),
-                        args.output_dir.clone(),
-                        total_put_number,
-                        args.payload_size,
-                        args.clone(),
-                        start,
+                        worker_config.clone(),
                         None,
                         None,
                     )
@@ -357,23 +487,7 @@ async fn test_worker_1(args: Cli) {
     let remaining_fut = (total_put_number - remaining..total_put_number)
         .into_par_iter()
         .map(|peer_index| {
-            publish_worker(
-                zenoh.clone(),
-                start_until,
-                timeout,
-                peer_index,
-                args.num_msgs_per_peer,
-                get_msg_payload(args.payload_size, peer_index),
-                args.multipeer_mode,
-                args.locators.clone(),
-                args.output_dir.clone(),
-                total_put_number,
-                args.payload_size,
-                args.clone(),
-                start,
-                None,
-                None,
-            )
+            publish_worker(zenoh.clone(), peer_index, worker_config.clone(), None, None)
         })
         .collect::<Vec<_>>();
     let remain_futs = async_std::task::spawn(futures::future::join_all(remaining_fut));
@@ -384,20 +498,12 @@ async fn test_worker_1(args: Cli) {
 
     let all_pub_fut = futures::future::join_all(pub_futs);
 
-    let demo_fut = demonstration_worker(
-        rx,
-        total_put_number,
-        total_sub_number,
-        args.num_msgs_per_peer,
-        args.remote_pub_peers,
-        args.payload_size,
-        args.round_timeout,
-        args.clone(),
-    );
+    let demo_fut = demonstration_worker(rx, total_sub_number, worker_config.clone(), args.clone());
 
     drop(tx);
 
-    futures::join!(all_pub_fut, all_sub_fut, demo_fut);
+    let (_, _, result_path) = futures::join!(all_pub_fut, all_sub_fut, demo_fut);
     let zenoh = Arc::try_unwrap(zenoh).ok().unwrap();
     zenoh.close().await.unwrap();
+    result_path
 }
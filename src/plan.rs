@@ -0,0 +1,286 @@
+//! Experiment plans: a TOML file describing a sweep over the `Cli` parameter space.
+//!
+//! ```toml
+//! warm_up = 1          # runs discarded before the repetitions of every point
+//! repetitions = 3      # runs recorded for every point
+//! cool_down = 2000     # pause (ms) between two runs
+//!
+//! # Either sweep the cartesian product of the axes ...
+//! [axes]
+//! num_put_peer = [1, 10, 50]
+//! payload_size = [64, 1024]
+//! mode = ["pub_and_sub", "pub_sub_separate", "multipeer"]
+//! peer_topology = ["star", "ring", "full-mesh"]
+//! session_mode = ["peer", "client"]
+//!
+//! # ... or list the points to run explicitly.
+//! [[points]]
+//! num_put_peer = 10
+//! num_msgs_per_peer = 100
+//! routers = 2
+//! ```
+//!
+//! Parameters that are neither in the axes nor in a point are taken from the command line.
+//! As on the command line, a run cannot both go through routers and link the peers in a
+//! topology: the plan is rejected before its first run if one of its points does.
+
+use crate::{run_experiment, Cli};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zenoh_perf_core::{common::*, SessionMode, TopologyKind};
+
+/// The name of the results index written into the output directory.
+pub const PLAN_INDEX_FILE: &str = "plan_index.json";
+
+/// How the peers of a run are mapped to zenoh sessions.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanMode {
+    /// One session for each pair of publisher and subscriber.
+    PubAndSub,
+    /// Separate publishers and subscribers sharing a single session (`--pub-sub-separate`).
+    PubSubSeparate,
+    /// Separate publishers and subscribers, each with its own session
+    /// (`--pub-sub-separate --multipeer-mode`).
+    Multipeer,
+}
+
+/// One point of the parameter space. Unset parameters keep their command line value.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PlanPoint {
+    pub num_put_peer: Option<usize>,
+    pub num_sub_peer: Option<usize>,
+    pub num_msgs_per_peer: Option<usize>,
+    pub payload_size: Option<usize>,
+    pub round_timeout: Option<u64>,
+    pub mode: Option<PlanMode>,
+    /// The graph the peers are linked in, by name (e.g. "ring"), in the `pub_and_sub` mode.
+    pub peer_topology: Option<TopologyKind>,
+    /// The length of the chain of in-process routers the sessions go through.
+    pub routers: Option<usize>,
+    pub session_mode: Option<SessionMode>,
+}
+
+/// The values to sweep for each parameter. Empty axes are not swept.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlanAxes {
+    pub num_put_peer: Vec<usize>,
+    pub num_sub_peer: Vec<usize>,
+    pub num_msgs_per_peer: Vec<usize>,
+    pub payload_size: Vec<usize>,
+    pub round_timeout: Vec<u64>,
+    pub mode: Vec<PlanMode>,
+    pub peer_topology: Vec<TopologyKind>,
+    pub routers: Vec<usize>,
+    pub session_mode: Vec<SessionMode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Plan {
+    /// The number of runs discarded before the repetitions of every point.
+    #[serde(default)]
+    pub warm_up: usize,
+    /// The number of runs recorded for every point.
+    #[serde(default = "default_repetitions")]
+    pub repetitions: usize,
+    /// The pause between two runs. Unit: milliseconds (ms).
+    #[serde(default)]
+    pub cool_down: u64,
+    #[serde(default)]
+    pub axes: PlanAxes,
+    /// Explicitly listed points. When present, the axes are ignored.
+    #[serde(default)]
+    pub points: Vec<PlanPoint>,
+}
+
+/// One recorded run of the plan, as written into the results index.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlanRun {
+    pub point: usize,
+    pub repetition: usize,
+    pub params: PlanPoint,
+    /// The `Exp_*.json` file of the run, relative to the output directory.
+    pub result_file: PathBuf,
+}
+
+/// The results index of a plan, referencing the result file of every run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlanIndex {
+    pub plan: Plan,
+    pub runs: Vec<PlanRun>,
+}
+
+fn default_repetitions() -> usize {
+    1
+}
+
+impl PlanAxes {
+    /// The cartesian product of all the non-empty axes.
+    pub fn points(&self) -> Vec<PlanPoint> {
+        let mut points = vec![PlanPoint::default()];
+        macro_rules! sweep {
+            ($field:ident) => {
+                if !self.$field.is_empty() {
+                    points = points
+                        .into_iter()
+                        .flat_map(|point| {
+                            self.$field.iter().map(move |value| PlanPoint {
+                                $field: Some(value.clone()),
+                                ..point.clone()
+                            })
+                        })
+                        .collect();
+                }
+            };
+        }
+        sweep!(num_put_peer);
+        sweep!(num_sub_peer);
+        sweep!(num_msgs_per_peer);
+        sweep!(payload_size);
+        sweep!(round_timeout);
+        sweep!(mode);
+        sweep!(peer_topology);
+        sweep!(routers);
+        sweep!(session_mode);
+        points
+    }
+}
+
+impl PlanPoint {
+    /// Overrides the parameters of `base` with the ones set in this point. Fails if the resulting
+    /// parameters conflict, which `Cli` checks for the command line only.
+    pub fn apply(&self, base: &Cli) -> Result<Cli> {
+        let mut args = base.clone();
+        args.plan = None;
+        if let Some(num_put_peer) = self.num_put_peer {
+            args.num_put_peer = num_put_peer;
+        }
+        if let Some(num_sub_peer) = self.num_sub_peer {
+            args.num_sub_peer = num_sub_peer;
+        }
+        if let Some(num_msgs_per_peer) = self.num_msgs_per_peer {
+            args.num_msgs_per_peer = num_msgs_per_peer;
+        }
+        if let Some(payload_size) = self.payload_size {
+            args.payload_size = payload_size;
+        }
+        if let Some(round_timeout) = self.round_timeout {
+            args.round_timeout = round_timeout;
+        }
+        match self.mode {
+            Some(PlanMode::PubAndSub) => {
+                args.pub_sub_separate = false;
+                args.multipeer_mode = false;
+            }
+            Some(PlanMode::PubSubSeparate) => {
+                args.pub_sub_separate = true;
+                args.multipeer_mode = false;
+            }
+            Some(PlanMode::Multipeer) => {
+                args.pub_sub_separate = true;
+                args.multipeer_mode = true;
+            }
+            None => {}
+        }
+        if let Some(peer_topology) = self.peer_topology {
+            args.peer_topology = Some(peer_topology);
+        }
+        if let Some(routers) = self.routers {
+            args.routers = routers;
+        }
+        if let Some(session_mode) = self.session_mode {
+            args.session_mode = session_mode;
+        }
+        if args.routers > 0 && args.peer_topology.is_some() {
+            anyhow::bail!(
+                "{:?}: routers and peer_topology cannot be used together",
+                self
+            );
+        }
+        Ok(args)
+    }
+}
+
+impl Plan {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let plan: Plan = toml::from_str(&content)?;
+        if plan.repetitions == 0 {
+            anyhow::bail!("the plan must have at least one repetition");
+        }
+        Ok(plan)
+    }
+
+    /// The points to run, either listed explicitly or swept from the axes.
+    pub fn points(&self) -> Vec<PlanPoint> {
+        if self.points.is_empty() {
+            self.axes.points()
+        } else {
+            self.points.clone()
+        }
+    }
+}
+
+/// Runs every point of the plan in `plan_path`, using `base` for the parameters the plan leaves
+/// unset. Each run writes into its own directory under `base.output_dir`, and the results index
+/// is rewritten after every run so that an interrupted plan still references its finished runs.
+pub async fn run_plan(plan_path: &Path, base: Cli) -> Result<()> {
+    let plan = Plan::from_file(plan_path)?;
+    let points = plan.points();
+    let point_args = points
+        .iter()
+        .map(|point| point.apply(&base))
+        .collect::<Result<Vec<_>>>()?;
+    println!(
+        "Running {} points x ({} warm-up + {} repetitions) from {:?}",
+        points.len(),
+        plan.warm_up,
+        plan.repetitions,
+        plan_path
+    );
+    std::fs::create_dir_all(&base.output_dir)?;
+    let mut index = PlanIndex {
+        plan: plan.clone(),
+        runs: vec![],
+    };
+    let mut first_run = true;
+
+    for (point_idx, (point, point_args)) in points.iter().zip(point_args.iter()).enumerate() {
+        for run_idx in 0..plan.warm_up + plan.repetitions {
+            if !first_run && plan.cool_down > 0 {
+                async_std::task::sleep(Duration::from_millis(plan.cool_down)).await;
+            }
+            first_run = false;
+
+            let mut args = point_args.clone();
+            let run_dir = if run_idx < plan.warm_up {
+                format!("point_{}_warm_up_{}", point_idx, run_idx)
+            } else {
+                format!("point_{}_rep_{}", point_idx, run_idx - plan.warm_up)
+            };
+            args.output_dir = base.output_dir.join(&run_dir);
+            std::fs::create_dir_all(&args.output_dir)?;
+            println!("Point {} ({:?}): run {}", point_idx, point, run_dir);
+
+            let result_path = run_experiment(args).await;
+            if run_idx < plan.warm_up {
+                continue;
+            }
+            index.runs.push(PlanRun {
+                point: point_idx,
+                repetition: run_idx - plan.warm_up,
+                params: point.clone(),
+                result_file: result_path
+                    .strip_prefix(&base.output_dir)
+                    .unwrap_or(&result_path)
+                    .to_path_buf(),
+            });
+            let mut file = std::fs::File::create(base.output_dir.join(PLAN_INDEX_FILE))?;
+            writeln!(&mut file, "{}", serde_json::to_string_pretty(&index)?)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Priority scenario: the latency of a high-priority low-rate flow, measured while low-priority
+//! publishers flood the same session.
+//!
+//! Publisher 0 sends at `priority_rate` with the `real-time` priority and the `block` congestion
+//! control, the other publishers send flat out with the `data-low` priority and the `drop`
+//! congestion control. All the publishers share one session, connected to the session of the
+//! subscribers, so that both flows go through the same queues. The `class_stats` of the result
+//! file have the latency and the lost messages of each flow.
+
+use crate::Cli;
+use std::path::PathBuf;
+use zenoh_perf_core::{
+    common::*, demonstration_worker, publish_worker, subscribe_worker, CongestionMode, PacingMode,
+    PriorityClass, PublisherGroup, SessionRole, SubReport, TestMode, Transport, TransportSetup,
+    ZenohConfigs,
+};
+
+/// How long the publishers send when no throughput duration is given. Unit: milliseconds (ms).
+const DEFAULT_FLOOD_DURATION: u64 = 5000;
+
+/// Runs the priority scenario with the other parameters of `args` and returns the path of its
+/// `Exp_*.json` result file.
+pub async fn run_priority_flood(mut args: Cli) -> Result<PathBuf> {
+    if args.num_put_peer < 2 {
+        anyhow::bail!("the priority scenario needs at least 2 publishers, one of them flooding");
+    }
+    if args.test_mode != TestMode::PubSub {
+        anyhow::bail!("the priority scenario only runs in pub-sub mode");
+    }
+    args.pub_groups = vec![
+        PublisherGroup {
+            name: "priority".to_string(),
+            pub_num: 1,
+            priority: PriorityClass::RealTime,
+            congestion_control: CongestionMode::Block,
+            pub_rate: Some(args.priority_rate),
+        },
+        PublisherGroup {
+            name: "flood".to_string(),
+            pub_num: args.num_put_peer - 1,
+            priority: PriorityClass::DataLow,
+            congestion_control: CongestionMode::Drop,
+            pub_rate: None,
+        },
+    ];
+    // The flooding publishers send flat out, for as long as the priority flow.
+    args.pacing = PacingMode::Interval;
+    args.pub_interval_freq = 0;
+    args.throughput_duration = args.throughput_duration.or(Some(DEFAULT_FLOOD_DURATION));
+    args.pub_sub_separate = true;
+    args.multipeer_mode = false;
+    std::fs::create_dir_all(&args.output_dir)?;
+    let transport_setup = if args.locators.is_empty() {
+        let transport_setup = TransportSetup::loopback(Transport::Tcp, &args.output_dir)?;
+        args.locators = vec![transport_setup.locator()];
+        args.transport_setup = Some(transport_setup.clone());
+        Some(transport_setup)
+    } else {
+        None
+    };
+    args.zenoh_configs = ZenohConfigs::load(&args.zenoh_config_files())?;
+    println!(
+        "Priority scenario: 1 publisher at {} msgs/s, {} flooding publishers, {} subscribers, for {} ms",
+        args.priority_rate,
+        args.num_put_peer - 1,
+        args.num_sub_peer,
+        args.throughput_duration.unwrap()
+    );
+
+    let (tx, rx) = flume::unbounded::<SubReport>();
+    let mut worker_config = args.worker_config(Instant::now());
+    let sub_session = Arc::new(
+        worker_config
+            .open_worker_session(SessionRole::Listener)
+            .await,
+    );
+    // The session shared by all the publishers.
+    let pub_session = Arc::new(
+        worker_config
+            .open_worker_session(SessionRole::Publisher(0))
+            .await,
+    );
+    worker_config.start = Instant::now();
+
+    let sub_futs = (0..args.num_sub_peer)
+        .map(|peer_id| {
+            async_std::task::spawn(subscribe_worker(
+                sub_session.clone(),
+                peer_id,
+                tx.clone(),
+                worker_config.clone(),
+                None,
+                None,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let pub_futs = (0..args.num_put_peer)
+        .map(|peer_id| {
+            async_std::task::spawn(publish_worker(
+                pub_session.clone(),
+                peer_id,
+                worker_config.clone(),
+                None,
+                None,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let demo_fut = demonstration_worker(rx, args.num_sub_peer, worker_config.clone(), args.clone());
+    drop(tx);
+
+    let (pub_results, sub_results, result_path) = futures::join!(
+        futures::future::join_all(pub_futs),
+        futures::future::join_all(sub_futs),
+        demo_fut
+    );
+    pub_results
+        .into_iter()
+        .chain(sub_results)
+        .collect::<Result<Vec<_>>>()?;
+    for session in [pub_session, sub_session] {
+        let session = Arc::try_unwrap(session).ok().unwrap();
+        session.close().await.unwrap();
+    }
+    if let Some(transport_setup) = transport_setup {
+        transport_setup.clean_up();
+    }
+    Ok(result_path)
+}
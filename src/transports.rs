@@ -0,0 +1,98 @@
+//! Transport matrix: the same experiment run over several transports on the loopback.
+//!
+//! Every run gets its own directory `transport_<name>` under the output directory, and
+//! `transport_matrix.json` compares the receive rate, latency and goodput of the transports.
+
+use crate::{run_experiment, Cli};
+use std::io::Write;
+use std::path::PathBuf;
+use zenoh_perf_core::{
+    common::*, LatencyStats, TestResult, ThroughputStats, Transport, TransportSetup,
+};
+
+/// The name of the comparison written into the output directory.
+pub const TRANSPORT_MATRIX_FILE: &str = "transport_matrix.json";
+
+/// The results of the experiment over one transport.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransportRun {
+    pub transport: Transport,
+    pub locator: String,
+    /// The `Exp_*.json` file of the run, relative to the output directory.
+    pub result_file: PathBuf,
+    pub total_receive_rate: f64,
+    pub latency: Option<LatencyStats>,
+    pub throughput: Option<ThroughputStats>,
+}
+
+/// Runs the experiment of `base` over each of `base.transports`, one after the other, and writes
+/// the comparison after every run so that an interrupted matrix still has its finished runs.
+pub async fn run_transport_matrix(base: Cli) -> Result<()> {
+    std::fs::create_dir_all(&base.output_dir)?;
+    let mut runs = vec![];
+
+    for transport in base.transports.iter().copied() {
+        let mut args = base.clone();
+        args.transports = vec![];
+        // Only the sessions of the separate publishers and subscribers connect to the listening
+        // session, the other modes would not go through the transport.
+        args.pub_sub_separate = true;
+        args.multipeer_mode = true;
+        args.output_dir = base
+            .output_dir
+            .join(format!("transport_{}", transport.name()));
+        std::fs::create_dir_all(&args.output_dir)?;
+        let transport_setup = TransportSetup::loopback(transport, &args.output_dir)?;
+        println!(
+            "Transport {}: listening on {}",
+            transport.name(),
+            transport_setup.locator
+        );
+        args.locators = vec![transport_setup.locator()];
+        args.transport_setup = Some(transport_setup.clone());
+
+        let result_path = run_experiment(args).await;
+        transport_setup.clean_up();
+        let result: TestResult<serde_json::Value> =
+            serde_json::from_slice(&std::fs::read(&result_path)?)?;
+        runs.push(TransportRun {
+            transport,
+            locator: transport_setup.locator,
+            result_file: result_path
+                .strip_prefix(&base.output_dir)
+                .unwrap_or(&result_path)
+                .to_path_buf(),
+            total_receive_rate: result.total_receive_rate,
+            latency: result.latency,
+            throughput: result.throughput,
+        });
+        let mut file = std::fs::File::create(base.output_dir.join(TRANSPORT_MATRIX_FILE))?;
+        writeln!(&mut file, "{}", serde_json::to_string_pretty(&runs)?)?;
+    }
+
+    println!(
+        "{:<16} {:>12} {:>10} {:>10} {:>14}",
+        "transport", "receive rate", "p50 (us)", "p99 (us)", "goodput (B/s)"
+    );
+    for run in runs.iter() {
+        let latency = |value: fn(&LatencyStats) -> u64| {
+            run.latency
+                .as_ref()
+                .map_or("-".to_string(), |latency| value(latency).to_string())
+        };
+        println!(
+            "{:<16} {:>12.4} {:>10} {:>10} {:>14}",
+            run.transport.name(),
+            run.total_receive_rate,
+            latency(|latency| latency.p50),
+            latency(|latency| latency.p99),
+            run.throughput
+                .as_ref()
+                .map_or("-".to_string(), |throughput| format!(
+                    "{:.1}",
+                    throughput.goodput_bytes_per_sec
+                ))
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,5 @@
+//! Parsing and analysis of the CPU and memory usage logs written by psrecord.
+
+pub mod phases;
+pub mod psrecord;
+pub mod stats;
@@ -1,11 +1,13 @@
-#![allow(unused)]
-use log::*;
-use pretty_env_logger;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
-use std::vec;
+use std::io::{self, Write};
+use std::path::PathBuf;
 use structopt::StructOpt;
+use usage_parser::{
+    phases::{phase_usages, PhaseBoundaries, PhaseUsage, PHASE_NAMES},
+    psrecord::{read_samples, UsageSample},
+    stats::ColumnStats,
+};
 
 #[derive(Debug, StructOpt)]
 struct Cli {
@@ -13,56 +15,258 @@ struct Cli {
         parse(from_os_str),
         short = "i",
         long = "input",
-        help = "path to input log file generated by psrecord"
+        required = true,
+        help = "paths to input log files generated by psrecord, several logs are compared in a single table"
     )]
-    path: std::path::PathBuf,
+    paths: Vec<PathBuf>,
+    #[structopt(
+        long = "from",
+        help = "only analyze the samples recorded from this elapsed time (s)"
+    )]
+    from: Option<f64>,
+    #[structopt(
+        long = "to",
+        help = "only analyze the samples recorded up to this elapsed time (s)"
+    )]
+    to: Option<f64>,
+    #[structopt(
+        short = "f",
+        long = "format",
+        default_value = "text",
+        possible_values = &["text", "json", "csv"],
+        help = "output format"
+    )]
+    format: String,
+    #[structopt(
+        parse(from_os_str),
+        short = "o",
+        long = "output",
+        help = "path to the output file, the standard output if not set"
+    )]
+    output: Option<PathBuf>,
+    #[structopt(
+        parse(from_os_str),
+        long = "exp-dir",
+        requires = "exp-name",
+        help = "path to the output directory of the experiment, to attribute the resource usage to its phases (init, session open, sending, receiving, teardown)"
+    )]
+    exp_dir: Option<PathBuf>,
+    #[structopt(
+        long = "exp-name",
+        help = "the name of the experiment in --exp-dir, the suffix of its `*_info_<name>.json` files (e.g. 2-2-100-40-100-1000)"
+    )]
+    exp_name: Option<String>,
+    #[structopt(
+        long = "offset",
+        default_value = "0",
+        help = "the time (s) between the start of the psrecord log and the start of the experiment, with --exp-dir"
+    )]
+    offset: f64,
+    #[structopt(
+        long = "skip-invalid",
+        help = "leave the logs that cannot be read out of the output instead of exiting with an error"
+    )]
+    skip_invalid: bool,
 }
 
-fn main() {
-    pretty_env_logger::init();
-    let args = Cli::from_args();
-    let mut max_cpu_usage: f64 = 0.0;
-    let mut max_real_memory_usage: f64 = 0.0;
-    let mut max_virt_memory_usage: f64 = 0.0;
-    if let Ok(lines) = read_lines(args.path) {
-        let mut cnt = 0;
-        for line in lines {
-            if cnt > 0 {
-                if let Ok(l) = line {
-                    let vec_string = l.split(" ").filter(|&x| !x.is_empty()).collect::<Vec<_>>();
-                    if vec_string.len() != 4 {
-                        warn!("vector format incorrect, discarding this line!");
-                    } else if vec_string[1].parse::<f64>().is_err() {
-                        warn!("element #1 in vec_string cannot be parsed as f64, discarding this line!");
-                    } else if vec_string[2].parse::<f64>().is_err() {
-                        warn!("element #2 in vec_string cannot be parsed as f64, discarding this line!");
-                    } else if vec_string[3].parse::<f64>().is_err() {
-                        warn!("element #3 in vec_string cannot be parsed as f64, discarding this line!");
-                    } else {
-                        if vec_string[1].parse::<f64>().unwrap() > max_cpu_usage {
-                            max_cpu_usage = vec_string[1].parse::<f64>().unwrap();
-                        }
-                        if vec_string[2].parse::<f64>().unwrap() > max_real_memory_usage {
-                            max_real_memory_usage = vec_string[2].parse::<f64>().unwrap();
-                        }
-                        if vec_string[3].parse::<f64>().unwrap() > max_virt_memory_usage {
-                            max_virt_memory_usage = vec_string[3].parse::<f64>().unwrap();
-                        }
+/// The statistics of one psrecord log.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct LogUsage {
+    path: PathBuf,
+    sample_num: usize,
+    /// Unit: percent of one core (%).
+    cpu: Option<ColumnStats>,
+    /// Unit: megabytes (MB).
+    real_memory: Option<ColumnStats>,
+    /// Unit: megabytes (MB).
+    virtual_memory: Option<ColumnStats>,
+    /// The resources used during each phase of the experiment, with `--exp-dir`.
+    phases: Option<Vec<PhaseUsage>>,
+}
+
+impl LogUsage {
+    fn new(path: PathBuf, samples: &[UsageSample], phases: Option<Vec<PhaseUsage>>) -> Self {
+        let column = |value: fn(&UsageSample) -> f64| {
+            ColumnStats::from_values(&samples.iter().map(value).collect::<Vec<_>>())
+        };
+        LogUsage {
+            path,
+            sample_num: samples.len(),
+            cpu: column(|sample| sample.cpu),
+            real_memory: column(|sample| sample.real_memory),
+            virtual_memory: column(|sample| sample.virtual_memory),
+            phases,
+        }
+    }
+
+    fn columns(&self) -> [Option<ColumnStats>; 3] {
+        [self.cpu, self.real_memory, self.virtual_memory]
+    }
+}
+
+/// The names of the columns of `LogUsage::columns` in the text output.
+const COLUMN_NAMES: [&str; 3] = ["CPU (%)", "Real Mem (MB)", "Virt Mem (MB)"];
+
+/// One table per column, with a row per log.
+fn write_text<W: Write>(out: &mut W, usages: &[LogUsage]) -> io::Result<()> {
+    for (column, name) in COLUMN_NAMES.iter().enumerate() {
+        writeln!(
+            out,
+            "{:<14} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}  log",
+            name, "samples", "max", "mean", "median", "p95", "stddev"
+        )?;
+        for usage in usages {
+            match usage.columns()[column] {
+                Some(stats) => writeln!(
+                    out,
+                    "{:<14} {:>8} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}  {}",
+                    "",
+                    usage.sample_num,
+                    stats.max,
+                    stats.mean,
+                    stats.median,
+                    stats.p95,
+                    stats.stddev,
+                    usage.path.display()
+                )?,
+                None => writeln!(
+                    out,
+                    "{:<14} {:>8} {:>54}  {}",
+                    "",
+                    0,
+                    "-",
+                    usage.path.display()
+                )?,
+            }
+        }
+        writeln!(out)?;
+    }
+    for usage in usages {
+        let phases = match &usage.phases {
+            Some(phases) => phases,
+            None => continue,
+        };
+        writeln!(out, "Phases of {}", usage.path.display())?;
+        writeln!(
+            out,
+            "{:<14} {:>8} {:>8} {:>12} {:>14} {:>14}",
+            "phase", "start", "end", "CPU-seconds", "Real Mem (MB)", "Virt Mem (MB)"
+        )?;
+        let peak = |memory: Option<f64>| memory.map_or("-".to_string(), |m| format!("{:.2}", m));
+        for phase in phases {
+            writeln!(
+                out,
+                "{:<14} {:>8.2} {:>8.2} {:>12.3} {:>14} {:>14}",
+                phase.phase,
+                phase.start,
+                phase.end,
+                phase.cpu_seconds,
+                peak(phase.peak_real_memory),
+                peak(phase.peak_virtual_memory)
+            )?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// A row per log, with the statistics of all the columns.
+fn write_csv<W: Write>(out: &mut W, usages: &[LogUsage]) -> io::Result<()> {
+    let mut header = vec!["log".to_string(), "samples".to_string()];
+    for column in ["cpu", "real_memory", "virtual_memory"] {
+        for stat in ["max", "mean", "median", "p95", "stddev"] {
+            header.push(format!("{}_{}", column, stat));
+        }
+    }
+    let with_phases = usages.iter().any(|usage| usage.phases.is_some());
+    if with_phases {
+        for phase in PHASE_NAMES {
+            for stat in ["cpu_seconds", "peak_real_memory", "peak_virtual_memory"] {
+                header.push(format!("{}_{}", phase, stat));
+            }
+        }
+    }
+    writeln!(out, "{}", header.join(","))?;
+    for usage in usages {
+        let mut row = vec![
+            format!("\"{}\"", usage.path.display()),
+            usage.sample_num.to_string(),
+        ];
+        for stats in usage.columns() {
+            match stats {
+                Some(stats) => row.extend(
+                    [stats.max, stats.mean, stats.median, stats.p95, stats.stddev]
+                        .iter()
+                        .map(|value| value.to_string()),
+                ),
+                None => row.extend(vec![String::new(); 5]),
+            }
+        }
+        if with_phases {
+            match &usage.phases {
+                Some(phases) => {
+                    let value = |value: Option<f64>| value.map_or(String::new(), |v| v.to_string());
+                    for phase in phases {
+                        row.push(phase.cpu_seconds.to_string());
+                        row.push(value(phase.peak_real_memory));
+                        row.push(value(phase.peak_virtual_memory));
                     }
                 }
+                None => row.extend(vec![String::new(); 3 * PHASE_NAMES.len()]),
             }
-            cnt += 1;
         }
+        writeln!(out, "{}", row.join(","))?;
     }
-    info!("Max CPU Usage \t\t= {}%", max_cpu_usage);
-    info!("Max real Mem Usage \t= {} MB", max_real_memory_usage);
-    info!("Max virt Mem Usage \t= {} MB", max_virt_memory_usage);
+    Ok(())
 }
 
-fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+fn main() {
+    pretty_env_logger::init();
+    let args = Cli::from_args();
+    let boundaries = args.exp_dir.as_ref().map(|exp_dir| {
+        let exp_name = args.exp_name.as_deref().unwrap();
+        PhaseBoundaries::from_exp_dir(exp_dir, exp_name, args.offset).unwrap_or_else(|err| {
+            panic!("Cannot read the phases of {}: {}", exp_dir.display(), err)
+        })
+    });
+    let mut invalid_num = 0;
+    let usages = args
+        .paths
+        .iter()
+        .filter_map(|path| match read_samples(path) {
+            Ok(samples) => {
+                let phases = boundaries
+                    .as_ref()
+                    .map(|boundaries| phase_usages(&samples, boundaries));
+                let samples = samples
+                    .into_iter()
+                    .filter(|sample| !matches!(args.from, Some(from) if sample.elapsed < from))
+                    .filter(|sample| !matches!(args.to, Some(to) if sample.elapsed > to))
+                    .collect::<Vec<_>>();
+                Some(LogUsage::new(path.clone(), &samples, phases))
+            }
+            Err(err) => {
+                eprintln!("Cannot read {}: {}", path.display(), err);
+                invalid_num += 1;
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if invalid_num > 0 && !args.skip_invalid {
+        eprintln!(
+            "{} log(s) cannot be read, use --skip-invalid to leave them out",
+            invalid_num
+        );
+        std::process::exit(1);
+    }
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path).unwrap()),
+        None => Box::new(io::stdout()),
+    };
+    match args.format.as_str() {
+        "json" => writeln!(out, "{}", serde_json::to_string_pretty(&usages).unwrap()).unwrap(),
+        "csv" => write_csv(&mut out, &usages).unwrap(),
+        _ => write_text(&mut out, &usages).unwrap(),
+    }
 }
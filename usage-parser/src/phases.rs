@@ -0,0 +1,179 @@
+use crate::psrecord::UsageSample;
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// The fields of `PubTimeStatus`/`SubTimeStatus` used to find the phases, written by the workers
+/// into `put_*_info_*.json` and `sub_*_info_*.json`. Unit: milliseconds (ms) since `start`.
+#[derive(Debug, Deserialize)]
+struct TimeStatus {
+    start_pub_worker: Option<u128>,
+    start_sub_worker: Option<u128>,
+    session_start: Option<u128>,
+    pub_sub_worker_start: Option<u128>,
+    start_sending: Option<u128>,
+    after_sending: Option<u128>,
+    start_receiving: Option<u128>,
+    after_receiving: Option<u128>,
+}
+
+/// The phases of an experiment, in order.
+pub const PHASE_NAMES: [&str; 5] = ["init", "session_open", "sending", "receiving", "teardown"];
+
+/// The boundaries of the phases, in seconds since the start of the psrecord log.
+///
+/// - `init`: until the first worker starts.
+/// - `session_open`: until the first publisher starts sending, covering the sessions being opened
+///   and the initialization time.
+/// - `sending`: until the last publisher is done sending.
+/// - `receiving`: until the last subscriber is done receiving.
+/// - `teardown`: until the end of the log.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct PhaseBoundaries {
+    pub workers_start: f64,
+    pub sending_start: f64,
+    pub sending_end: f64,
+    pub receiving_end: f64,
+}
+
+impl PhaseBoundaries {
+    /// Reads the time statuses of the experiment `exp_name` (e.g. `2-2-100-40-100-1000`, the
+    /// suffix of its `*_info_<exp_name>.json` files) in `exp_dir`, the other experiments written
+    /// into the same directory being ignored. `offset` is the time (s) between the start of the
+    /// psrecord log and the `start` instant of the experiment.
+    pub fn from_exp_dir(exp_dir: &Path, exp_name: &str, offset: f64) -> io::Result<Self> {
+        let suffix = format!("_info_{}.json", exp_name);
+        let mut statuses = vec![];
+        for entry in std::fs::read_dir(exp_dir)? {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            let is_status =
+                (name.starts_with("put_") || name.starts_with("sub_")) && name.ends_with(&suffix);
+            if !is_status {
+                continue;
+            }
+            match serde_json::from_slice::<TimeStatus>(&std::fs::read(&path)?) {
+                Ok(status) => statuses.push(status),
+                Err(err) => warn!("Cannot parse {}: {}", path.display(), err),
+            }
+        }
+        if statuses.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "no time status file of experiment {} in {}",
+                    exp_name,
+                    exp_dir.display()
+                ),
+            ));
+        }
+
+        let to_secs = |millis: Option<u128>| millis.map(|millis| offset + millis as f64 / 1000.0);
+        let min = |field: fn(&TimeStatus) -> Option<u128>| {
+            statuses
+                .iter()
+                .filter_map(|status| to_secs(field(status)))
+                .reduce(f64::min)
+        };
+        let max = |field: fn(&TimeStatus) -> Option<u128>| {
+            statuses
+                .iter()
+                .filter_map(|status| to_secs(field(status)))
+                .reduce(f64::max)
+        };
+        let workers_start = [
+            min(|status| status.pub_sub_worker_start),
+            min(|status| status.start_pub_worker),
+            min(|status| status.start_sub_worker),
+        ]
+        .iter()
+        .flatten()
+        .copied()
+        .reduce(f64::min)
+        .unwrap_or(offset);
+        let sending_start = min(|status| status.start_sending)
+            .or_else(|| min(|status| status.start_receiving))
+            .or_else(|| max(|status| status.session_start))
+            .unwrap_or(workers_start)
+            .max(workers_start);
+        let sending_end = max(|status| status.after_sending)
+            .unwrap_or(sending_start)
+            .max(sending_start);
+        let receiving_end = max(|status| status.after_receiving)
+            .unwrap_or(sending_end)
+            .max(sending_end);
+        Ok(PhaseBoundaries {
+            workers_start,
+            sending_start,
+            sending_end,
+            receiving_end,
+        })
+    }
+
+    /// The (start, end) of every phase of `PHASE_NAMES`, the last one ending at `log_end`.
+    fn ranges(&self, log_end: f64) -> [(f64, f64); 5] {
+        [
+            (0.0, self.workers_start),
+            (self.workers_start, self.sending_start),
+            (self.sending_start, self.sending_end),
+            (self.sending_end, self.receiving_end),
+            (self.receiving_end, log_end.max(self.receiving_end)),
+        ]
+    }
+}
+
+/// The resources used during one phase.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PhaseUsage {
+    pub phase: String,
+    /// Unit: seconds (s) since the start of the psrecord log.
+    pub start: f64,
+    pub end: f64,
+    /// The CPU time used during the phase, 1 being one core fully used for one second.
+    pub cpu_seconds: f64,
+    /// Unit: megabytes (MB). `None` if no sample falls in the phase.
+    pub peak_real_memory: Option<f64>,
+    pub peak_virtual_memory: Option<f64>,
+}
+
+/// Attributes the samples to the phases. psrecord measures the CPU usage of a sample since the
+/// previous one, this interval being split between the phases it overlaps. The CPU usages that
+/// are not finite are left out.
+pub fn phase_usages(samples: &[UsageSample], boundaries: &PhaseBoundaries) -> Vec<PhaseUsage> {
+    let log_end = samples.last().map_or(0.0, |sample| sample.elapsed);
+    boundaries
+        .ranges(log_end)
+        .iter()
+        .zip(PHASE_NAMES.iter())
+        .map(|((start, end), phase)| {
+            let cpu_seconds = samples
+                .windows(2)
+                .filter(|pair| pair[1].cpu.is_finite())
+                .map(|pair| {
+                    let overlap = pair[1].elapsed.min(*end) - pair[0].elapsed.max(*start);
+                    pair[1].cpu / 100.0 * overlap.max(0.0)
+                })
+                .sum();
+            let in_phase = samples
+                .iter()
+                .filter(|sample| sample.elapsed >= *start && sample.elapsed <= *end);
+            PhaseUsage {
+                phase: phase.to_string(),
+                start: *start,
+                end: *end,
+                cpu_seconds,
+                peak_real_memory: in_phase
+                    .clone()
+                    .map(|sample| sample.real_memory)
+                    .reduce(f64::max),
+                peak_virtual_memory: in_phase
+                    .map(|sample| sample.virtual_memory)
+                    .reduce(f64::max),
+            }
+        })
+        .collect()
+}
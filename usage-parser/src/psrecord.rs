@@ -0,0 +1,223 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/// One line of a psrecord log.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UsageSample {
+    /// The time since psrecord started. Unit: seconds (s).
+    pub elapsed: f64,
+    /// Unit: percent of one core (%).
+    pub cpu: f64,
+    /// Unit: megabytes (MB).
+    pub real_memory: f64,
+    /// Unit: megabytes (MB).
+    pub virtual_memory: f64,
+}
+
+/// The columns of a psrecord log the samples are read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Elapsed,
+    Cpu,
+    RealMemory,
+    VirtualMemory,
+}
+
+impl Column {
+    const ALL: [Column; 4] = [
+        Column::Elapsed,
+        Column::Cpu,
+        Column::RealMemory,
+        Column::VirtualMemory,
+    ];
+
+    /// The beginning of the name of the column in the header, in lower case.
+    fn prefix(&self) -> &'static str {
+        match self {
+            Column::Elapsed => "elapsed",
+            Column::Cpu => "cpu",
+            Column::RealMemory => "real",
+            Column::VirtualMemory => "virtual",
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Column::Elapsed => "Elapsed time",
+            Column::Cpu => "CPU (%)",
+            Column::RealMemory => "Real (MB)",
+            Column::VirtualMemory => "Virtual (MB)",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    Io(io::Error),
+    /// The log does not start with a `#` header line.
+    MissingHeader,
+    /// The header has no column for this value.
+    MissingColumn(Column),
+    /// A line does not have as many values as the header has columns.
+    ColumnNumber {
+        expected: usize,
+        found: usize,
+    },
+    /// A value is not a number.
+    InvalidNumber {
+        column: Column,
+        value: String,
+    },
+}
+
+/// An error while reading a psrecord log. `line` is 1-based, 0 if the error is not tied to a line.
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: ", self.line)?;
+        }
+        match &self.kind {
+            ParseErrorKind::Io(err) => write!(f, "{}", err),
+            ParseErrorKind::MissingHeader => write!(f, "missing '#' header line"),
+            ParseErrorKind::MissingColumn(column) => {
+                write!(f, "no '{}' column in the header", column)
+            }
+            ParseErrorKind::ColumnNumber { expected, found } => {
+                write!(f, "expected {} values, found {}", expected, found)
+            }
+            ParseErrorKind::InvalidNumber { column, value } => {
+                write!(f, "invalid '{}' value {:?}", column, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The position of the columns in the lines of a log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// The number of values on every line.
+    pub column_num: usize,
+    /// The index of every column of `Column::ALL`.
+    indices: [usize; 4],
+}
+
+impl Header {
+    /// Parses the header line, e.g. `# Elapsed time   CPU (%)     Real (MB)   Virtual (MB)`.
+    ///
+    /// psrecord centers the names in 12 characters, so longer names are only separated by a single
+    /// space: a name ends with its unit in parentheses, except `Elapsed time`, and the words
+    /// outside of parentheses left at the end are names on their own.
+    ///
+    /// The columns other than the ones of `Column` are ignored. psrecord writes the same columns
+    /// with `--include-children`, the usage of the children being included in them; the columns
+    /// whose name mentions children, which psrecord does not write, are ignored as well.
+    pub fn parse(line: &str) -> Result<Self, ParseErrorKind> {
+        let mut names = vec![];
+        let mut words: Vec<String> = vec![];
+        for word in line
+            .strip_prefix('#')
+            .ok_or(ParseErrorKind::MissingHeader)?
+            .split_whitespace()
+        {
+            words.push(word.to_lowercase());
+            if word.ends_with(')') || words.join(" ") == "elapsed time" {
+                names.push(words.join(" "));
+                words.clear();
+            }
+        }
+        names.extend(words);
+        let mut indices = [0; 4];
+        for (column, index) in Column::ALL.iter().zip(indices.iter_mut()) {
+            *index = names
+                .iter()
+                .position(|name| name.starts_with(column.prefix()) && !name.contains("child"))
+                .ok_or(ParseErrorKind::MissingColumn(*column))?;
+        }
+        Ok(Header {
+            column_num: names.len(),
+            indices,
+        })
+    }
+
+    /// Parses a line of values.
+    pub fn parse_sample(&self, line: &str) -> Result<UsageSample, ParseErrorKind> {
+        let values = line.split_whitespace().collect::<Vec<_>>();
+        if values.len() != self.column_num {
+            return Err(ParseErrorKind::ColumnNumber {
+                expected: self.column_num,
+                found: values.len(),
+            });
+        }
+        let mut parsed = [0.0; 4];
+        for ((column, index), value) in Column::ALL
+            .iter()
+            .zip(self.indices.iter())
+            .zip(parsed.iter_mut())
+        {
+            *value = values[*index]
+                .parse()
+                .map_err(|_| ParseErrorKind::InvalidNumber {
+                    column: *column,
+                    value: values[*index].to_string(),
+                })?;
+        }
+        let [elapsed, cpu, real_memory, virtual_memory] = parsed;
+        Ok(UsageSample {
+            elapsed,
+            cpu,
+            real_memory,
+            virtual_memory,
+        })
+    }
+}
+
+/// Parses a psrecord log, the first line being the header. The empty lines are skipped.
+pub fn parse_samples<R: BufRead>(reader: R) -> Result<Vec<UsageSample>, ParseError> {
+    let mut lines = reader.lines().enumerate();
+    let error = |line: usize| move |kind| ParseError { line, kind };
+    let header = match lines.next() {
+        Some((_, line)) => {
+            let line = line.map_err(|err| error(1)(ParseErrorKind::Io(err)))?;
+            Header::parse(&line).map_err(error(1))?
+        }
+        None => return Err(error(0)(ParseErrorKind::MissingHeader)),
+    };
+    let mut samples = vec![];
+    for (index, line) in lines {
+        let line = line.map_err(|err| error(index + 1)(ParseErrorKind::Io(err)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        samples.push(header.parse_sample(&line).map_err(error(index + 1))?);
+    }
+    Ok(samples)
+}
+
+/// Reads the samples of the psrecord log at `path`.
+pub fn read_samples<P: AsRef<Path>>(path: P) -> Result<Vec<UsageSample>, ParseError> {
+    let file = File::open(path).map_err(|err| ParseError {
+        line: 0,
+        kind: ParseErrorKind::Io(err),
+    })?;
+    parse_samples(io::BufReader::new(file))
+}
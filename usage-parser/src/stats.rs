@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+/// The summary statistics of one column of a log.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ColumnStats {
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+    /// The population standard deviation.
+    pub stddev: f64,
+}
+
+/// The value below which `p` percent of the sorted `values` fall, interpolated linearly between
+/// the two closest values.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+impl ColumnStats {
+    /// The non-finite values (NaN, infinities) are left out. Returns `None` if no value is left.
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        let values = values
+            .iter()
+            .copied()
+            .filter(|value| value.is_finite())
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.clone();
+        // The values are finite, so they are totally ordered.
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / values.len() as f64;
+        Some(ColumnStats {
+            max: sorted[sorted.len() - 1],
+            mean,
+            median: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            stddev: variance.sqrt(),
+        })
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+use usage_parser::phases::{phase_usages, PhaseBoundaries};
+use usage_parser::psrecord::UsageSample;
+
+fn exp_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("exp")
+}
+
+#[test]
+fn only_the_statuses_of_the_experiment_are_read() {
+    let boundaries = PhaseBoundaries::from_exp_dir(&exp_dir(), "2-2-10-40-100-1000", 0.5).unwrap();
+    assert_eq!(boundaries.workers_start, 0.505);
+    assert_eq!(boundaries.sending_start, 1.5);
+    assert_eq!(boundaries.sending_end, 2.0);
+    assert_eq!(boundaries.receiving_end, 2.2);
+}
+
+#[test]
+fn other_experiment_in_the_same_dir() {
+    let boundaries = PhaseBoundaries::from_exp_dir(&exp_dir(), "2-2-10-40-100-500", 0.0).unwrap();
+    assert_eq!(boundaries.workers_start, 0.001);
+    assert_eq!(boundaries.sending_end, 9.0);
+    assert_eq!(boundaries.receiving_end, 9.0);
+}
+
+#[test]
+fn non_finite_cpu_is_left_out_of_the_phases() {
+    let boundaries = PhaseBoundaries {
+        workers_start: 1.0,
+        sending_start: 2.0,
+        sending_end: 3.0,
+        receiving_end: 4.0,
+    };
+    let samples = [0.0, 50.0, f64::NAN, f64::INFINITY, 100.0]
+        .iter()
+        .enumerate()
+        .map(|(i, cpu)| UsageSample {
+            elapsed: i as f64,
+            cpu: *cpu,
+            real_memory: 20.0,
+            virtual_memory: 300.0,
+        })
+        .collect::<Vec<_>>();
+    let cpu_seconds = phase_usages(&samples, &boundaries)
+        .iter()
+        .map(|usage| usage.cpu_seconds)
+        .collect::<Vec<_>>();
+    assert_eq!(cpu_seconds, vec![0.5, 0.0, 0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn unknown_experiment() {
+    let err = PhaseBoundaries::from_exp_dir(&exp_dir(), "1-1-1-40-100-1000", 0.0).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
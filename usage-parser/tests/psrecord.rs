@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use usage_parser::psrecord::{
+    parse_samples, read_samples, Column, Header, ParseErrorKind, UsageSample,
+};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+#[test]
+fn basic_log() {
+    let samples = read_samples(fixture("basic.log")).unwrap();
+    assert_eq!(samples.len(), 10);
+    assert_eq!(samples[0].elapsed, 0.0);
+    assert!(samples
+        .windows(2)
+        .all(|pair| pair[0].elapsed < pair[1].elapsed));
+    assert!(samples
+        .iter()
+        .all(|sample| sample.real_memory > 0.0 && sample.virtual_memory > sample.real_memory));
+}
+
+#[test]
+fn include_children_log() {
+    let samples = read_samples(fixture("include_children.log")).unwrap();
+    assert_eq!(samples.len(), 8);
+    assert!(samples.iter().any(|sample| sample.cpu > 100.0));
+}
+
+#[test]
+fn extra_columns_are_ignored() {
+    let samples = read_samples(fixture("extra_columns.log")).unwrap();
+    assert_eq!(samples.len(), 6);
+    assert_eq!(samples[0].real_memory, 12.621);
+    assert_eq!(samples[0].virtual_memory, 313.006);
+}
+
+/// psrecord does not write columns about the children, the log is written by hand.
+#[test]
+fn synthetic_children_columns_are_ignored() {
+    let samples = read_samples(fixture("synthetic_children_columns.log")).unwrap();
+    let expected = (0..5)
+        .map(|i| UsageSample {
+            elapsed: i as f64 * 0.5,
+            cpu: 40.0 + i as f64,
+            real_memory: 20.0 + i as f64,
+            virtual_memory: 300.0 + i as f64,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(samples, expected);
+}
+
+#[test]
+fn invalid_number() {
+    let err = read_samples(fixture("invalid_number.log")).unwrap_err();
+    assert_eq!(err.line, 5);
+    match err.kind {
+        ParseErrorKind::InvalidNumber { column, value } => {
+            assert_eq!(column, Column::Cpu);
+            assert_eq!(value, "nan%");
+        }
+        kind => panic!("unexpected error {:?}", kind),
+    }
+}
+
+#[test]
+fn truncated_line() {
+    let err = read_samples(fixture("truncated_line.log")).unwrap_err();
+    assert_eq!(err.line, 4);
+    assert!(matches!(
+        err.kind,
+        ParseErrorKind::ColumnNumber {
+            expected: 4,
+            found: 3
+        }
+    ));
+}
+
+#[test]
+fn missing_header() {
+    let err = read_samples(fixture("missing_header.log")).unwrap_err();
+    assert_eq!(err.line, 1);
+    assert!(matches!(err.kind, ParseErrorKind::MissingHeader));
+}
+
+#[test]
+fn missing_file() {
+    let err = read_samples(fixture("does_not_exist.log")).unwrap_err();
+    assert_eq!(err.line, 0);
+    assert!(matches!(err.kind, ParseErrorKind::Io(_)));
+}
+
+#[test]
+fn empty_log() {
+    let err = parse_samples("".as_bytes()).unwrap_err();
+    assert!(matches!(err.kind, ParseErrorKind::MissingHeader));
+}
+
+#[test]
+fn missing_column() {
+    let err = Header::parse("# Elapsed time   CPU (%)     Real (MB)").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseErrorKind::MissingColumn(Column::VirtualMemory)
+    ));
+}
+
+#[test]
+fn reordered_columns() {
+    let log = "# Virtual (MB)   CPU (%)   Elapsed time   Real (MB)\n\n1.5 2.5 0.5 3.5\n";
+    let samples = parse_samples(log.as_bytes()).unwrap();
+    assert_eq!(
+        samples,
+        vec![UsageSample {
+            elapsed: 0.5,
+            cpu: 2.5,
+            real_memory: 3.5,
+            virtual_memory: 1.5,
+        }]
+    );
+}
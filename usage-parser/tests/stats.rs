@@ -0,0 +1,26 @@
+use usage_parser::stats::ColumnStats;
+
+#[test]
+fn column_stats() {
+    let stats = ColumnStats::from_values(&[4.0, 1.0, 3.0, 2.0]).unwrap();
+    assert_eq!(stats.max, 4.0);
+    assert_eq!(stats.mean, 2.5);
+    assert_eq!(stats.median, 2.5);
+    assert!((stats.p95 - 3.85).abs() < 1e-9);
+    assert!((stats.stddev - 1.25f64.sqrt()).abs() < 1e-9);
+}
+
+#[test]
+fn non_finite_values_are_left_out() {
+    let values = [2.0, f64::NAN, 1.0, f64::INFINITY, 3.0, f64::NEG_INFINITY];
+    let stats = ColumnStats::from_values(&values).unwrap();
+    assert_eq!(stats.max, 3.0);
+    assert_eq!(stats.mean, 2.0);
+    assert_eq!(stats.median, 2.0);
+}
+
+#[test]
+fn no_finite_value() {
+    assert!(ColumnStats::from_values(&[]).is_none());
+    assert!(ColumnStats::from_values(&[f64::NAN, f64::INFINITY]).is_none());
+}
@@ -0,0 +1,153 @@
+use crate::{
+    common::*,
+    config::WorkerConfig,
+    coordinator::CONTROL_ROOT,
+    result::{PubTimeStatus, SubTimeStatus},
+    workers::{demonstration_worker, SubReport},
+};
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The key expression worker `peer_id` publishes its results on.
+pub fn result_key_expr(peer_id: usize) -> String {
+    format!("{}/results/{}", CONTROL_ROOT, peer_id)
+}
+
+/// The key expression the collector listens to the results on.
+pub const RESULT_KEY_EXPR: &str = "/perf/results/*";
+
+/// Everything a worker process writes into its `output_dir` at the end of a round, sent to the
+/// collector instead.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkerResult {
+    pub peer_id: usize,
+    pub report: SubReport,
+    pub pub_time_status: Option<PubTimeStatus>,
+    pub sub_time_status: Option<SubTimeStatus>,
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(file_path: &Path) -> Option<T> {
+    let json = std::fs::read(file_path).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+fn write_json<T: Serialize>(file_path: &Path, value: &T) {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(value).unwrap()
+    )
+    .unwrap();
+}
+
+/// Publishes the report of worker `report.peer_id` to the collector, together with the time
+/// statuses its publisher and subscriber wrote into `config.output_dir`.
+pub async fn publish_result(session: &Session, report: SubReport, config: &WorkerConfig) {
+    let peer_id = report.peer_id;
+    let exp_name = config.exp_name();
+    let result = WorkerResult {
+        peer_id,
+        pub_time_status: read_json(
+            &config
+                .output_dir
+                .join(format!("put_{}_info_{}.json", peer_id, exp_name)),
+        ),
+        sub_time_status: read_json(
+            &config
+                .output_dir
+                .join(format!("sub_{}_info_{}.json", peer_id, exp_name)),
+        ),
+        report,
+    };
+    session
+        .put(
+            result_key_expr(peer_id).as_str(),
+            serde_json::to_vec(&result).unwrap(),
+        )
+        .await
+        .unwrap();
+}
+
+/// Receives the results of `peer_num` workers, or the ones received before `timeout`, and
+/// assembles them with `demonstration_worker` as if the workers had run in this process.
+///
+/// The time statuses of the workers are written into `config.output_dir` under the names the
+/// workers use, next to the `Exp_*.json` result file whose path is returned.
+pub async fn collector_worker<C: Serialize>(
+    session: &Session,
+    peer_num: usize,
+    config: WorkerConfig,
+    args: C,
+    timeout: Duration,
+) -> PathBuf {
+    let (tx, rx) = flume::unbounded::<SubReport>();
+    let demo_fut = demonstration_worker(rx, peer_num, config.clone(), args);
+    let collect_fut = async move {
+        let mut subscriber = session.subscribe(RESULT_KEY_EXPR).await.unwrap();
+        let collect_end = async_std::task::sleep(timeout);
+        futures::pin_mut!(collect_end);
+        let mut results = subscriber.receiver().take_until(collect_end);
+        let mut collected_peers = BTreeSet::new();
+        let exp_name = config.exp_name();
+        while collected_peers.len() < peer_num {
+            let sample = match results.next().await {
+                Some(sample) => sample,
+                None => {
+                    warn!(
+                        "Collected the results of {}/{} workers before the timeout",
+                        collected_peers.len(),
+                        peer_num
+                    );
+                    break;
+                }
+            };
+            let result: WorkerResult =
+                match serde_json::from_slice(&sample.value.payload.contiguous()) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        debug!("Received a malformed worker result: {}", err);
+                        continue;
+                    }
+                };
+            if !collected_peers.insert(result.peer_id) {
+                continue;
+            }
+            info!(
+                "collected the results of worker {} ({}/{})",
+                result.peer_id,
+                collected_peers.len(),
+                peer_num
+            );
+            if let Some(pub_time_status) = &result.pub_time_status {
+                config
+                    .sent_msg_nums
+                    .report(result.peer_id, pub_time_status.sent_msg_num);
+                write_json(
+                    &config
+                        .output_dir
+                        .join(format!("put_{}_info_{}.json", result.peer_id, exp_name)),
+                    pub_time_status,
+                );
+            }
+            if let Some(sub_time_status) = &result.sub_time_status {
+                write_json(
+                    &config
+                        .output_dir
+                        .join(format!("sub_{}_info_{}.json", result.peer_id, exp_name)),
+                    sub_time_status,
+                );
+            }
+            tx.send_async(result.report).await.unwrap();
+        }
+    };
+    let (_, result_path) = futures::join!(collect_fut, demo_fut);
+    result_path
+}
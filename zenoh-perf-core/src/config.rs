@@ -0,0 +1,281 @@
+use crate::{
+    common::*,
+    keyspace::{KeySpace, KeyTopology, SubscriptionKind},
+    pacing::PacingMode,
+    payload::PayloadFiller,
+    qos::PublisherQos,
+    result::ShortConfig,
+    router::SessionMode,
+    stats::SeqTracker,
+    topology::PeerEndpoints,
+    transport::TransportSetup,
+    workers::SentMsgNums,
+    zenoh_config::{add_connect, add_listen, ZenohConfigs},
+};
+use std::path::PathBuf;
+
+/// What the workers of an experiment exchange.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum TestMode {
+    /// Publishers `put` messages that subscribers receive.
+    PubSub,
+    /// Queriers `get` replies from queryables, the publisher peers answering the queries of the
+    /// subscriber peers.
+    Query,
+    /// Pingers send pings that pongers echo back, the publisher peers answering the pings of the
+    /// subscriber peers.
+    PingPong,
+}
+
+/// What a session is opened for, which decides the router it connects to and the config
+/// overrides it gets.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SessionRole {
+    /// The session of a publisher in multipeer mode, by peer id.
+    Publisher(usize),
+    /// The session of a subscriber in multipeer mode, by peer id.
+    Subscriber(usize),
+    /// The session of a peer both publishing and subscribing, by peer id.
+    PubSub(usize),
+    /// The session listening on the locators, shared by the separate publishers and subscribers
+    /// outside of multipeer mode.
+    Listener,
+    /// An in-process router, by index in the chain.
+    Router(usize),
+}
+
+/// The parameters shared by all the workers of one experiment round.
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    /// The path to store the output .json files.
+    pub output_dir: PathBuf,
+    /// Whether the publisher/subscriber peers put and subscribe or answer and send queries.
+    pub test_mode: TestMode,
+    /// How the keys of the publishers are laid out.
+    pub key_topology: KeyTopology,
+    /// Whether subscribers use a wildcard or exact subscriptions.
+    pub subscription: SubscriptionKind,
+    /// The number of topics with `KeyTopology::Topics`.
+    pub topic_num: usize,
+    /// The number of intermediate levels in every key.
+    pub key_depth: usize,
+    /// The total number of local publisher peers.
+    pub total_put_number: usize,
+    /// The number of publisher peers running on remote machines.
+    pub remote_pub_peers: usize,
+    /// The number of messages each publisher peer will try to send.
+    pub num_msgs_per_peer: usize,
+    /// The payload size (bytes) of the message.
+    pub payload_size: usize,
+    /// The bytes used to fill the payload after the header.
+    pub payload_filler: PayloadFiller,
+    /// The timeout (ms) for subscribers to stop receiving messages.
+    pub round_timeout: u64,
+    /// The initialization time (ms) for starting up futures.
+    pub init_time: u64,
+    /// Create a new zenoh session for each publisher/subscriber worker.
+    pub multipeer_mode: bool,
+    /// The locators each session connects to.
+    pub locators: Vec<Locator>,
+    /// How publishers space out their messages.
+    pub pacing: PacingMode,
+    /// The interval (ms) between the messages published by publisher, with `Interval` pacing.
+    pub pub_interval: u64,
+    /// The frequency (messages) to add the pub_interval. 0 turns it off.
+    pub pub_interval_freq: usize,
+    /// The rate (messages/s) each publisher sends at, with the rate-based pacings.
+    pub pub_rate: Option<f64>,
+    /// The maximum number of messages sent back to back with `TokenBucket` pacing.
+    pub burst_size: usize,
+    /// Busy-wait the end of the waits of the rate-based pacings, for a sub-millisecond accuracy.
+    pub spin_wait: bool,
+    /// The priority, congestion control and rate of the publishers.
+    pub publisher_qos: PublisherQos,
+    /// Run in throughput mode: publishers send for this duration (ms) instead of sending
+    /// `num_msgs_per_peer` messages, and subscribers keep receiving `round_timeout` ms longer.
+    pub throughput_duration: Option<u64>,
+    /// The time (ms) a querier waits for all the replies to a query, or a pinger for all the pongs
+    /// to a ping.
+    pub reply_timeout: u64,
+    /// Sample the CPU and memory usage of the process at this interval (ms) during the round.
+    pub resource_interval: Option<u64>,
+    /// The instant the round starts from. All the recorded time statuses are relative to it.
+    pub start: Instant,
+    /// The wall-clock time of `start`, recorded by the multi-process workers.
+    pub process_start: Option<SystemTime>,
+    /// The transport the sessions are restricted to, in a transport matrix run.
+    pub transport_setup: Option<TransportSetup>,
+    /// The endpoints of every peer, indexed by peer id, when the peers are linked in a topology.
+    /// Empty when all the sessions connect to `locators`.
+    pub peer_endpoints: Vec<PeerEndpoints>,
+    /// The mode the sessions of the workers are opened in.
+    pub session_mode: SessionMode,
+    /// The endpoints of the in-process routers, in the order of the chain. Empty when the
+    /// sessions do not go through routers.
+    pub router_endpoints: Vec<String>,
+    /// The zenoh config files the sessions are built from, which record the effective config of
+    /// every session.
+    pub zenoh_configs: ZenohConfigs,
+    /// Where the publishers report the number of messages they sent, read by
+    /// `demonstration_worker`.
+    pub sent_msg_nums: SentMsgNums,
+}
+
+impl WorkerConfig {
+    /// The instant publishers start sending and subscribers start receiving.
+    pub fn start_until(&self) -> Instant {
+        self.start + Duration::from_millis(self.init_time)
+    }
+
+    pub fn is_throughput_mode(&self) -> bool {
+        self.throughput_duration.is_some()
+    }
+
+    /// The instant publishers stop sending in throughput mode.
+    pub fn send_until(&self) -> Instant {
+        self.start_until() + Duration::from_millis(self.throughput_duration.unwrap_or(0))
+    }
+
+    /// The instant subscribers stop receiving.
+    pub fn timeout(&self) -> Instant {
+        self.send_until() + Duration::from_millis(self.round_timeout)
+    }
+
+    /// The key expressions of the experiment. Queries and pings always reach every responder,
+    /// so the topology only applies to `TestMode::PubSub`.
+    pub fn key_space(&self) -> KeySpace {
+        KeySpace {
+            topology: match self.test_mode {
+                TestMode::PubSub => self.key_topology,
+                TestMode::Query | TestMode::PingPong => KeyTopology::Shared,
+            },
+            subscription: self.subscription,
+            topic_num: self.topic_num,
+            depth: self.key_depth,
+            pub_num: self.total_put_number + self.remote_pub_peers,
+        }
+    }
+
+    /// The number of messages every publisher sends, when known before the round: the higher
+    /// sequence numbers are rejected as corrupted.
+    pub fn seq_limit(&self) -> Option<usize> {
+        if self.is_throughput_mode() {
+            None
+        } else {
+            Some(self.num_msgs_per_peer)
+        }
+    }
+
+    /// The number of messages subscriber `sub_peer_id` expects to receive, i.e. the messages of
+    /// the publishers whose key it subscribes to.
+    /// In throughput mode, it is only known once the messages are received (see `SubReport`).
+    pub fn expected_msg_num(&self, sub_peer_id: usize) -> usize {
+        self.key_space().matched_pub_num(sub_peer_id) * self.num_msgs_per_peer
+    }
+
+    /// The number of messages subscriber `sub_peer_id` expects from publisher `pub_peer_id`,
+    /// tracked by `tracker`.
+    /// In throughput mode, the subscriber does not know how many messages were sent, so the
+    /// messages lost after the last one received are not counted.
+    pub fn expected_msg_num_from(
+        &self,
+        pub_peer_id: usize,
+        sub_peer_id: usize,
+        tracker: &SeqTracker,
+    ) -> usize {
+        if self.is_throughput_mode() {
+            tracker.seq_span()
+        } else if self.key_space().matches(pub_peer_id, sub_peer_id) {
+            self.num_msgs_per_peer
+        } else {
+            0
+        }
+    }
+
+    /// The experiment parameters used in the output file names, i.e.
+    /// `{put}-{put}-{msgs}-{payload}-{timeout}-{init}`.
+    pub fn exp_name(&self) -> String {
+        format!(
+            "{}-{}-{}-{}-{}-{}",
+            self.total_put_number,
+            self.total_put_number,
+            self.num_msgs_per_peer,
+            self.payload_size,
+            self.round_timeout,
+            self.init_time
+        )
+    }
+
+    pub fn short_config(&self, peer_id: usize) -> ShortConfig {
+        ShortConfig {
+            peer_id,
+            total_put_number: self.total_put_number,
+            num_msgs_per_peer: self.num_msgs_per_peer,
+            payload_size: self.payload_size,
+            round_timeout: self.round_timeout,
+            init_time: self.init_time,
+        }
+    }
+
+    /// The config of the session opened for `role`: the zenoh config files of the role, to
+    /// which the harness adds its endpoints:
+    /// - the listener listens on `locators`, restricted to `transport_setup` if set;
+    /// - with routers, the session connects to the first router as a publisher, to the last one
+    ///   as a subscriber, so that the messages go through the whole chain, and the publishing and
+    ///   subscribing peers are spread over the routers;
+    /// - with a topology, the peer listens on its own endpoint and connects to its neighbours;
+    /// - otherwise, the session connects to `locators`, restricted to `transport_setup` if set.
+    pub fn worker_session_config(&self, role: SessionRole) -> Config {
+        let mut config = self.zenoh_configs.config(role);
+        let locators = self
+            .locators
+            .iter()
+            .map(|locator| EndPoint::from(locator.clone()));
+        if role == SessionRole::Listener {
+            add_listen(&mut config, locators);
+            if let Some(transport_setup) = &self.transport_setup {
+                transport_setup.apply(&mut config);
+            }
+            return config;
+        }
+        config.set_mode(Some(self.session_mode.whatami())).unwrap();
+        if !self.router_endpoints.is_empty() {
+            let router_num = self.router_endpoints.len();
+            let router = match role {
+                SessionRole::Publisher(_) => 0,
+                SessionRole::Subscriber(_) => router_num - 1,
+                SessionRole::PubSub(peer_id) => peer_id % router_num,
+                SessionRole::Listener | SessionRole::Router(_) => {
+                    unreachable!("{:?} is not a worker session", role)
+                }
+            };
+            add_connect(
+                &mut config,
+                [self.router_endpoints[router].parse().unwrap()],
+            );
+            // The sessions must not find each other without the routers.
+            config
+                .insert_json5("scouting/multicast/enabled", "false")
+                .unwrap();
+            return config;
+        }
+        if let SessionRole::PubSub(peer_id) = role {
+            if let Some(endpoints) = self.peer_endpoints.get(peer_id) {
+                endpoints.apply(&mut config);
+                return config;
+            }
+        }
+        add_connect(&mut config, locators);
+        if let Some(transport_setup) = &self.transport_setup {
+            transport_setup.apply(&mut config);
+        }
+        config
+    }
+
+    /// Opens the zenoh session of a worker, see `worker_session_config`, and records its config.
+    pub async fn open_worker_session(&self, role: SessionRole) -> Session {
+        let config = self.worker_session_config(role);
+        self.zenoh_configs.record(role, &config);
+        zenoh::open(config).await.unwrap()
+    }
+}
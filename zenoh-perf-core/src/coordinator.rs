@@ -0,0 +1,158 @@
+use crate::{common::*, config::WorkerConfig};
+use std::collections::BTreeSet;
+
+/// The root of the control and result keys, outside of the keys of the data (see `KeySpace`), so
+/// that the subscribers never receive them as samples.
+pub const CONTROL_ROOT: &str = "/perf";
+
+/// The key expression worker `peer_id` announces it is ready on.
+pub fn ready_key_expr(peer_id: usize) -> String {
+    format!("{}/control/ready/{}", CONTROL_ROOT, peer_id)
+}
+
+/// The key expression the coordinator listens to the ready announcements on.
+pub const READY_KEY_EXPR: &str = "/perf/control/ready/*";
+
+/// The key expression the coordinator broadcasts the start of the round on.
+pub const START_KEY_EXPR: &str = "/perf/control/start";
+
+/// How often a worker announces it is ready until it receives the start of the round, in case the
+/// coordinator was not listening yet.
+const READY_RESEND_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The announcement of a worker that its control session is up and waiting for the start.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ReadyMsg {
+    pub peer_id: usize,
+}
+
+/// The round parameters the coordinator imposes on all the workers, so that the processes agree
+/// on them whatever their own CLI says.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct RoundParams {
+    pub init_time: u64,
+    pub round_timeout: u64,
+    pub num_msgs_per_peer: usize,
+    pub payload_size: usize,
+    pub throughput_duration: Option<u64>,
+}
+
+impl RoundParams {
+    pub fn from_config(config: &WorkerConfig) -> Self {
+        RoundParams {
+            init_time: config.init_time,
+            round_timeout: config.round_timeout,
+            num_msgs_per_peer: config.num_msgs_per_peer,
+            payload_size: config.payload_size,
+            throughput_duration: config.throughput_duration,
+        }
+    }
+
+    /// Overrides the round parameters of `config`.
+    pub fn apply(&self, config: &mut WorkerConfig) {
+        config.init_time = self.init_time;
+        config.round_timeout = self.round_timeout;
+        config.num_msgs_per_peer = self.num_msgs_per_peer;
+        config.payload_size = self.payload_size;
+        config.throughput_duration = self.throughput_duration;
+    }
+}
+
+/// The start of the round, broadcast by the coordinator once every worker is ready.
+///
+/// The start is sent as a delay rather than as a wall-clock time, so that the workers agree on it
+/// up to the delivery time of the message even if the clocks of the machines are not synchronized.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct StartMsg {
+    /// The time left before the start when the message was sent. Unit: microseconds (us).
+    pub start_in: u64,
+    pub params: RoundParams,
+}
+
+async fn broadcast_start(session: &Session, start: Instant, params: RoundParams) -> Result<()> {
+    let msg = StartMsg {
+        start_in: start.saturating_duration_since(Instant::now()).as_micros() as u64,
+        params,
+    };
+    session
+        .put(START_KEY_EXPR, serde_json::to_vec(&msg)?)
+        .await
+        .unwrap();
+    Ok(())
+}
+
+/// Waits until `peer_num` distinct workers announce they are ready, then broadcasts a
+/// common start `start_delay` later together with `params`.
+///
+/// The ready announcements received after the broadcast and before the start are answered with
+/// the start again, for the workers which missed it. Returns the start instant.
+pub async fn coordinator_worker(
+    session: &Session,
+    peer_num: usize,
+    params: RoundParams,
+    start_delay: Duration,
+) -> Result<Instant> {
+    let mut subscriber = session.subscribe(READY_KEY_EXPR).await.unwrap();
+    let announcements = subscriber.receiver();
+    let mut ready_peers = BTreeSet::new();
+    info!("waiting for {} workers", peer_num);
+    while ready_peers.len() < peer_num {
+        let sample = match announcements.next().await {
+            Some(sample) => sample,
+            None => anyhow::bail!("the ready subscription was closed"),
+        };
+        let msg: ReadyMsg = match serde_json::from_slice(&sample.value.payload.contiguous()) {
+            Ok(msg) => msg,
+            Err(err) => {
+                debug!("Received a malformed ready announcement: {}", err);
+                continue;
+            }
+        };
+        if ready_peers.insert(msg.peer_id) {
+            info!(
+                "worker {} ready ({}/{})",
+                msg.peer_id,
+                ready_peers.len(),
+                peer_num
+            );
+        }
+    }
+
+    let start = Instant::now() + start_delay;
+    broadcast_start(session, start, params).await?;
+    let start_sleep = async_std::task::sleep(start_delay);
+    futures::pin_mut!(start_sleep);
+    let mut late_announcements = announcements.take_until(start_sleep);
+    while late_announcements.next().await.is_some() {
+        broadcast_start(session, start, params).await?;
+    }
+
+    Ok(start)
+}
+
+/// Announces worker `peer_id` to the coordinator until the start of the round is received.
+/// Returns the local instant of the start and the round parameters.
+pub async fn wait_for_start(session: &Session, peer_id: usize) -> Result<(Instant, RoundParams)> {
+    let mut subscriber = session.subscribe(START_KEY_EXPR).await.unwrap();
+    let starts = subscriber.receiver();
+    let ready_key = ready_key_expr(peer_id);
+    let ready = serde_json::to_vec(&ReadyMsg { peer_id })?;
+    loop {
+        session
+            .put(ready_key.as_str(), ready.clone())
+            .await
+            .unwrap();
+        let sample = match async_std::future::timeout(READY_RESEND_INTERVAL, starts.next()).await {
+            Ok(Some(sample)) => sample,
+            Ok(None) => anyhow::bail!("the start subscription was closed"),
+            Err(_) => continue,
+        };
+        let received = Instant::now();
+        match serde_json::from_slice::<StartMsg>(&sample.value.payload.contiguous()) {
+            Ok(msg) => {
+                return Ok((received + Duration::from_micros(msg.start_in), msg.params));
+            }
+            Err(err) => debug!("Received a malformed start: {}", err),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use crate::common::*;
+use std::collections::BTreeMap;
+
+/// The number of bits of a value kept by its bucket. Values below `2^SUB_BUCKET_BITS` have
+/// their own bucket, larger ones share a bucket with the values differing by less than
+/// `1 / 2^(SUB_BUCKET_BITS - 1)` of them (i.e. 3 significant digits).
+const SUB_BUCKET_BITS: u32 = 11;
+
+/// A log-linear (HDR style) histogram of latencies in nanoseconds.
+///
+/// Only the non-empty buckets are stored, so a histogram stays small whatever the number of
+/// recorded values. Histograms are serialized losslessly, as the list of `[bucket, count]` pairs
+/// where `bucket` is the lowest value of the bucket, and merging them gives exactly the histogram
+/// of all the values recorded into them.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    pub count: u64,
+    /// The sum of the recorded values, for an exact mean.
+    pub sum: u128,
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    #[serde(with = "bucket_list")]
+    pub buckets: BTreeMap<u64, u64>,
+}
+
+/// Serializes the buckets as a list of `[bucket, count]` pairs instead of a map with string keys.
+mod bucket_list {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(
+        buckets: &BTreeMap<u64, u64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(buckets.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<u64, u64>, D::Error> {
+        Ok(Vec::<(u64, u64)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// The lowest value of the bucket `value` falls in.
+fn bucket_of(value: u64) -> u64 {
+    let magnitude = 64 - value.leading_zeros();
+    if magnitude <= SUB_BUCKET_BITS {
+        value
+    } else {
+        let shift = magnitude - SUB_BUCKET_BITS;
+        (value >> shift) << shift
+    }
+}
+
+/// The highest value falling in the bucket starting at `bucket`.
+fn bucket_end(bucket: u64) -> u64 {
+    let magnitude = 64 - bucket.leading_zeros();
+    if magnitude <= SUB_BUCKET_BITS {
+        bucket
+    } else {
+        bucket + ((1 << (magnitude - SUB_BUCKET_BITS)) - 1)
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, value: u64) {
+        self.count += 1;
+        self.sum += value as u128;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        *self.buckets.entry(bucket_of(value)).or_insert(0) += 1;
+    }
+
+    /// Adds all the values recorded into `other`.
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = match (self.min, other.min) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        for (bucket, count) in other.buckets.iter() {
+            *self.buckets.entry(*bucket).or_insert(0) += count;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.sum as f64 / self.count as f64)
+        }
+    }
+
+    /// The value below which `p` percent of the recorded values fall, up to the precision of the
+    /// buckets. Returns `None` if the histogram is empty.
+    pub fn value_at_percentile(&self, p: f64) -> Option<u64> {
+        let max = self.max?;
+        let rank = (((p / 100.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter() {
+            seen += count;
+            if seen >= rank {
+                return Some(bucket_end(*bucket).min(max));
+            }
+        }
+        Some(max)
+    }
+
+    /// The cumulative distribution of the recorded values: for every non-empty bucket, the
+    /// highest value of the bucket and the fraction of the values up to it.
+    pub fn cdf(&self) -> Vec<(u64, f64)> {
+        let max = match self.max {
+            Some(max) => max,
+            None => return vec![],
+        };
+        let mut seen = 0;
+        self.buckets
+            .iter()
+            .map(|(bucket, count)| {
+                seen += count;
+                (
+                    bucket_end(*bucket).min(max),
+                    seen as f64 / self.count as f64,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_have_their_own_bucket() {
+        for value in [0, 1, 1000, (1 << SUB_BUCKET_BITS) - 1] {
+            assert_eq!(bucket_of(value), value);
+            assert_eq!(bucket_end(value), value);
+        }
+    }
+
+    #[test]
+    fn buckets_contain_their_values() {
+        for value in [
+            1 << SUB_BUCKET_BITS,
+            12_345,
+            1_000_000,
+            987_654_321,
+            u64::MAX / 3,
+            u64::MAX,
+        ] {
+            let bucket = bucket_of(value);
+            assert!(bucket <= value && value <= bucket_end(bucket));
+            assert_eq!(bucket_of(bucket_end(bucket)), bucket);
+            if bucket_end(bucket) < u64::MAX {
+                assert_ne!(bucket_of(bucket_end(bucket) + 1), bucket);
+            }
+            // 3 significant digits.
+            assert!((bucket_end(bucket) - bucket) as f64 <= value as f64 / 1000.0);
+        }
+    }
+
+    #[test]
+    fn percentiles_of_uniform_values() {
+        let mut histogram = LatencyHistogram::new();
+        for value in 1..=1000 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.value_at_percentile(0.0), Some(1));
+        assert_eq!(histogram.value_at_percentile(50.0), Some(500));
+        assert_eq!(histogram.value_at_percentile(99.0), Some(990));
+        assert_eq!(histogram.value_at_percentile(100.0), Some(1000));
+        assert_eq!(histogram.mean(), Some(500.5));
+    }
+
+    #[test]
+    fn percentiles_stay_within_the_bucket_precision() {
+        let mut histogram = LatencyHistogram::new();
+        for value in (1..=10_000).map(|value| value * 1_000) {
+            histogram.record(value);
+        }
+        let p90 = histogram.value_at_percentile(90.0).unwrap();
+        assert!((p90 as f64 / 9_000_000.0 - 1.0).abs() < 0.001);
+        assert_eq!(histogram.value_at_percentile(100.0), Some(10_000_000));
+    }
+
+    #[test]
+    fn empty_histogram_has_no_statistics() {
+        let histogram = LatencyHistogram::new();
+        assert_eq!(histogram.value_at_percentile(50.0), None);
+        assert_eq!(histogram.mean(), None);
+        assert!(histogram.cdf().is_empty());
+    }
+
+    #[test]
+    fn merge_equals_recording_all_the_values() {
+        let values = (0..5000u64).map(|i| i * i % 7_919_993).collect::<Vec<_>>();
+        let (mut a, mut b, mut all) = (
+            LatencyHistogram::new(),
+            LatencyHistogram::new(),
+            LatencyHistogram::new(),
+        );
+        for (index, value) in values.iter().enumerate() {
+            if index % 3 == 0 {
+                a.record(*value);
+            } else {
+                b.record(*value);
+            }
+            all.record(*value);
+        }
+        a.merge(&b);
+        assert_eq!(a, all);
+        let mut empty = LatencyHistogram::new();
+        empty.merge(&all);
+        assert_eq!(empty, all);
+    }
+
+    #[test]
+    fn serde_round_trip_is_lossless() {
+        let mut histogram = LatencyHistogram::new();
+        for value in [3, 3, 70_000, 123_456_789] {
+            histogram.record(value);
+        }
+        let json = serde_json::to_string(&histogram).unwrap();
+        assert!(json.contains("\"buckets\":[[3,2],"));
+        let parsed: LatencyHistogram = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, histogram);
+    }
+
+    #[test]
+    fn cdf_ends_at_the_max() {
+        let mut histogram = LatencyHistogram::new();
+        for value in [1, 2, 2, 5_000_000] {
+            histogram.record(value);
+        }
+        assert_eq!(
+            histogram.cdf(),
+            vec![(1, 0.25), (2, 0.75), (5_000_000, 1.0)]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::common::*;
+
+/// How the keys of the publishers are laid out.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum KeyTopology {
+    /// Every publisher puts on `/demo/example/hello`, every subscriber receives from all of them.
+    Shared,
+    /// Publisher `p` puts on its own key `/bench/<p>`, every subscriber receives from all of them.
+    PerPublisher,
+    /// Publisher `p` puts on `/bench/topic/<p % topic_num>`, and subscriber `s` only receives the
+    /// topic `s % topic_num`.
+    Topics,
+}
+
+/// How subscribers declare their subscriptions.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum SubscriptionKind {
+    /// A single subscription with a wildcard key expression matching all the keys to receive.
+    Wildcard,
+    /// One subscription for each exact key to receive.
+    Exact,
+}
+
+/// The key expressions used by the publishers and subscribers of an experiment.
+#[derive(Debug, Clone, Copy)]
+pub struct KeySpace {
+    pub topology: KeyTopology,
+    pub subscription: SubscriptionKind,
+    /// The number of topics with `KeyTopology::Topics`.
+    pub topic_num: usize,
+    /// The number of intermediate levels (`/l0/l1/...`) inserted in every key.
+    pub depth: usize,
+    /// The number of publishers, local and remote. Publisher ids range from 0 to `pub_num - 1`.
+    pub pub_num: usize,
+}
+
+impl KeySpace {
+    fn root(&self) -> String {
+        let root = match self.topology {
+            KeyTopology::Shared => "/demo/example",
+            KeyTopology::PerPublisher | KeyTopology::Topics => "/bench",
+        };
+        (0..self.depth).fold(root.to_string(), |key, level| format!("{}/l{}", key, level))
+    }
+
+    fn topic_of(&self, peer_id: usize) -> usize {
+        peer_id % self.topic_num.max(1)
+    }
+
+    /// The key publisher `pub_id` puts on.
+    pub fn pub_key(&self, pub_id: usize) -> String {
+        match self.topology {
+            KeyTopology::Shared => format!("{}/hello", self.root()),
+            KeyTopology::PerPublisher => format!("{}/{}", self.root(), pub_id),
+            KeyTopology::Topics => format!("{}/topic/{}", self.root(), self.topic_of(pub_id)),
+        }
+    }
+
+    /// Whether subscriber `sub_id` receives the messages of publisher `pub_id`.
+    pub fn matches(&self, pub_id: usize, sub_id: usize) -> bool {
+        match self.topology {
+            KeyTopology::Shared | KeyTopology::PerPublisher => true,
+            KeyTopology::Topics => self.topic_of(pub_id) == self.topic_of(sub_id),
+        }
+    }
+
+    /// The number of publishers subscriber `sub_id` receives the messages of.
+    pub fn matched_pub_num(&self, sub_id: usize) -> usize {
+        (0..self.pub_num)
+            .filter(|pub_id| self.matches(*pub_id, sub_id))
+            .count()
+    }
+
+    /// The key expressions subscriber `sub_id` subscribes to.
+    pub fn sub_key_exprs(&self, sub_id: usize) -> Vec<String> {
+        match (self.subscription, self.topology) {
+            (SubscriptionKind::Wildcard, KeyTopology::Shared) => {
+                vec!["/demo/example/**".to_string()]
+            }
+            (SubscriptionKind::Wildcard, KeyTopology::PerPublisher) => {
+                vec!["/bench/**".to_string()]
+            }
+            (SubscriptionKind::Wildcard, KeyTopology::Topics) => {
+                vec![format!("/bench/**/topic/{}", self.topic_of(sub_id))]
+            }
+            (SubscriptionKind::Exact, KeyTopology::PerPublisher) => (0..self.pub_num)
+                .map(|pub_id| self.pub_key(pub_id))
+                .collect(),
+            (SubscriptionKind::Exact, _) => vec![self.pub_key(sub_id)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collector::{result_key_expr, RESULT_KEY_EXPR},
+        coordinator::{ready_key_expr, CONTROL_ROOT, READY_KEY_EXPR, START_KEY_EXPR},
+    };
+
+    #[test]
+    fn control_keys_are_outside_of_the_data_keys() {
+        let control_keys = [
+            ready_key_expr(3),
+            READY_KEY_EXPR.to_string(),
+            START_KEY_EXPR.to_string(),
+            result_key_expr(3),
+            RESULT_KEY_EXPR.to_string(),
+        ];
+        for topology in [
+            KeyTopology::Shared,
+            KeyTopology::PerPublisher,
+            KeyTopology::Topics,
+        ] {
+            for subscription in [SubscriptionKind::Wildcard, SubscriptionKind::Exact] {
+                let key_space = KeySpace {
+                    topology,
+                    subscription,
+                    topic_num: 2,
+                    depth: 1,
+                    pub_num: 4,
+                };
+                // The data keys and expressions are all literal up to their first wildcard.
+                let prefixes = (0..4)
+                    .flat_map(|id| {
+                        key_space
+                            .sub_key_exprs(id)
+                            .into_iter()
+                            .chain([key_space.pub_key(id)])
+                    })
+                    .map(|key| key.split("/*").next().unwrap().to_string())
+                    .collect::<Vec<_>>();
+                for prefix in prefixes {
+                    assert!(!prefix.starts_with(CONTROL_ROOT), "{}", prefix);
+                    for control_key in control_keys.iter() {
+                        assert!(
+                            !control_key.starts_with(&format!("{}/", prefix)),
+                            "{} matches {}",
+                            prefix,
+                            control_key
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Building blocks shared by the zenoh performance test binaries.
+//!
+//! The binaries only parse their own CLI, fill a [`WorkerConfig`] and spawn the workers
+//! exported here, so that every test uses the same payload format, workers and result schema.
+
+pub mod collector;
+pub mod common;
+pub mod config;
+pub mod coordinator;
+pub mod histogram;
+pub mod keyspace;
+pub mod pacing;
+pub mod payload;
+pub mod ping;
+pub mod qos;
+pub mod query;
+pub mod resources;
+pub mod result;
+pub mod router;
+pub mod session;
+pub mod stats;
+pub mod throughput;
+pub mod topology;
+pub mod transport;
+pub mod workers;
+pub mod zenoh_config;
+
+pub use collector::{collector_worker, publish_result, WorkerResult};
+pub use config::{SessionRole, TestMode, WorkerConfig};
+pub use coordinator::{coordinator_worker, wait_for_start, RoundParams};
+pub use histogram::LatencyHistogram;
+pub use keyspace::{KeySpace, KeyTopology, SubscriptionKind};
+pub use pacing::{Pacer, PacingMode};
+pub use payload::{get_msg_payload, MsgHeader, PayloadFiller};
+pub use ping::{ping_worker, pong_worker, PingResult};
+pub use qos::{ClassStats, CongestionMode, PriorityClass, PublisherGroup, PublisherQos};
+pub use query::{query_worker, queryable_worker};
+pub use resources::{ResourceSample, ResourceSampler};
+pub use result::{PeerResult, PubTimeStatus, ShortConfig, SubTimeStatus, TestResult};
+pub use router::{RouterChain, SessionMode};
+pub use session::{open_session, session_config, session_info_worker, SessionTimeStatus};
+pub use stats::{receive_rate, LatencyStats, PubSubStats, SeqTracker};
+pub use throughput::{ThroughputMeter, ThroughputStats, ThroughputWindow};
+pub use topology::{PeerEndpoints, Topology, TopologyKind};
+pub use transport::{TlsFiles, Transport, TransportSetup};
+pub use workers::{
+    demonstration_worker, pub_and_sub_worker, publish_worker, subscribe_worker, SentMsgNums,
+    SubReport,
+};
+pub use zenoh_config::{SessionConfigRecord, ZenohConfigFiles, ZenohConfigs};
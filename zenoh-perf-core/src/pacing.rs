@@ -0,0 +1,265 @@
+use crate::{common::*, config::WorkerConfig};
+
+/// Below this, the remaining time before a deadline is busy-waited instead of slept with
+/// `spin_wait`, since the async timers only have a millisecond granularity.
+const SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+
+/// How publishers space out the messages they send.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum PacingMode {
+    /// Sleep `pub_interval` ms after every `pub_interval_freq` messages. Flat out if either is 0.
+    Interval,
+    /// Send at a constant `pub_rate`.
+    Constant,
+    /// Send at `pub_rate` on average, allowing bursts of up to `burst_size` messages.
+    TokenBucket,
+    /// Send with exponentially distributed inter-arrival times of mean `1 / pub_rate`.
+    Poisson,
+}
+
+/// Computes the send deadline of every message of a publisher.
+///
+/// The deadlines of the rate-based modes are absolute, i.e. derived from the instant the
+/// publisher started sending rather than from the previous message, so that the time spent in
+/// `put` or a late wake-up does not accumulate into a lower rate.
+#[derive(Debug, Clone)]
+pub struct Pacer {
+    mode: PacingMode,
+    /// The mean interval between two messages of the rate-based modes.
+    period: Duration,
+    /// The time a full bucket holds, i.e. how far ahead of the average rate a burst may go.
+    burst_tolerance: Duration,
+    pub_interval: Duration,
+    pub_interval_freq: usize,
+    start: Instant,
+    /// The deadline of the next message (constant, Poisson) or the theoretical arrival time of
+    /// the next message at the average rate (token bucket).
+    next: Instant,
+    msg_num: usize,
+    /// xorshift64 state drawing the Poisson inter-arrival times.
+    rng_state: u64,
+    /// Whether the end of the waits is busy-waited, only with the rate-based modes.
+    spin: bool,
+}
+
+impl Pacer {
+    /// Creates the pacer of publisher `peer_id`, starting now. The publishers of a group with a
+    /// rate send at that constant rate, whatever the pacing of the experiment.
+    pub fn new(config: &WorkerConfig, peer_id: usize) -> Result<Self> {
+        let group_rate = config
+            .publisher_qos
+            .group(peer_id)
+            .and_then(|group| group.pub_rate);
+        let (mode, pub_rate) = match group_rate {
+            Some(rate) => (PacingMode::Constant, Some(rate)),
+            None => (config.pacing, config.pub_rate),
+        };
+        let period = match mode {
+            PacingMode::Interval => Duration::ZERO,
+            _ => match pub_rate {
+                Some(rate) if rate > 0.0 => Duration::from_secs_f64(1.0 / rate),
+                _ => anyhow::bail!(
+                    "{:?} pacing needs a positive publishing rate, got {:?}",
+                    mode,
+                    pub_rate
+                ),
+            },
+        };
+        let start = Instant::now();
+        Ok(Pacer {
+            mode,
+            period,
+            burst_tolerance: period * (config.burst_size.max(1) - 1) as u32,
+            pub_interval: Duration::from_millis(config.pub_interval),
+            pub_interval_freq: config.pub_interval_freq,
+            start,
+            next: start,
+            msg_num: 0,
+            rng_state: peer_id as u64 + 0x9E37_79B9_7F4A_7C15,
+            spin: config.spin_wait && mode != PacingMode::Interval,
+        })
+    }
+
+    /// The instant the next message may be sent at, `None` if it can be sent right away.
+    pub fn next_deadline(&mut self) -> Option<Instant> {
+        let msg_num = self.msg_num;
+        self.msg_num += 1;
+        let now = Instant::now();
+        let deadline = match self.mode {
+            PacingMode::Interval => {
+                // The historical behaviour: sleep after the 1st, (freq+1)-th, ... message.
+                if msg_num > 0
+                    && self.pub_interval_freq > 0
+                    && !self.pub_interval.is_zero()
+                    && (msg_num - 1) % self.pub_interval_freq == 0
+                {
+                    now + self.pub_interval
+                } else {
+                    now
+                }
+            }
+            PacingMode::Constant => self.start + self.period.mul_f64(msg_num as f64),
+            PacingMode::TokenBucket => {
+                // GCRA: a message conforms if it is at most `burst_tolerance` ahead of its
+                // theoretical arrival time. An idle publisher does not accumulate more credit
+                // than a full bucket.
+                let arrival = self.next.max(now);
+                self.next = arrival + self.period;
+                if arrival > now + self.burst_tolerance {
+                    arrival - self.burst_tolerance
+                } else {
+                    now
+                }
+            }
+            PacingMode::Poisson => {
+                let deadline = self.next;
+                let interval = self.period.mul_f64(self.exponential());
+                self.next += interval;
+                deadline
+            }
+        };
+        if deadline > now {
+            Some(deadline)
+        } else {
+            None
+        }
+    }
+
+    /// Waits until the next message may be sent.
+    pub async fn wait(&mut self) {
+        if let Some(deadline) = self.next_deadline() {
+            self.sleep_until(deadline).await;
+        }
+    }
+
+    /// Sleeps until `deadline`, spinning over the end of the wait if enabled.
+    pub async fn sleep_until(&self, deadline: Instant) {
+        if self.spin {
+            spin_until(deadline).await;
+        } else {
+            async_std::task::sleep(deadline.saturating_duration_since(Instant::now())).await;
+        }
+    }
+
+    /// Draws from the exponential distribution of mean 1.
+    fn exponential(&mut self) -> f64 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        // Uniform in (0, 1], so that the logarithm is finite.
+        let uniform = ((self.rng_state >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        -uniform.ln()
+    }
+}
+
+/// Sleeps until `deadline` with microsecond accuracy: the bulk of the wait is slept and the last
+/// `SPIN_THRESHOLD` is spent yielding to the executor, which keeps a core busy.
+pub async fn spin_until(deadline: Instant) {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining > SPIN_THRESHOLD {
+        async_std::task::sleep(remaining - SPIN_THRESHOLD).await;
+    }
+    while Instant::now() < deadline {
+        async_std::task::yield_now().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pacer(mode: PacingMode, period: Duration, burst_size: u32) -> Pacer {
+        let start = Instant::now();
+        Pacer {
+            mode,
+            period,
+            burst_tolerance: period * (burst_size.max(1) - 1),
+            pub_interval: Duration::from_millis(10),
+            pub_interval_freq: 3,
+            start,
+            next: start,
+            msg_num: 0,
+            rng_state: 0x9E37_79B9_7F4A_7C15,
+            spin: false,
+        }
+    }
+
+    #[test]
+    fn interval_sleeps_after_every_freq_messages() {
+        let mut pacer = pacer(PacingMode::Interval, Duration::ZERO, 1);
+        let waits = (0..8)
+            .map(|_| pacer.next_deadline().is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(waits, [false, true, false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn constant_deadlines_are_absolute() {
+        let period = Duration::from_secs(1);
+        let mut pacer = pacer(PacingMode::Constant, period, 1);
+        assert_eq!(pacer.next_deadline(), None);
+        for msg_num in 1..10u32 {
+            assert_eq!(pacer.next_deadline(), Some(pacer.start + period * msg_num));
+        }
+    }
+
+    #[test]
+    fn token_bucket_allows_bursts_of_burst_size() {
+        let period = Duration::from_secs(1);
+        let mut pacer = pacer(PacingMode::TokenBucket, period, 3);
+        for _ in 0..3 {
+            assert_eq!(pacer.next_deadline(), None);
+        }
+        // The 4th message is `burst_tolerance` ahead of its arrival time at the average rate.
+        let deadline = pacer.next_deadline().unwrap();
+        assert!(deadline > pacer.start + period / 2);
+        assert!(deadline <= Instant::now() + period);
+        // After the burst, the messages are spaced by the period.
+        let next = pacer.next_deadline().unwrap();
+        assert_eq!(next - deadline, period);
+    }
+
+    #[test]
+    fn token_bucket_does_not_accumulate_credit_when_idle() {
+        let period = Duration::from_secs(1);
+        let mut pacer = pacer(PacingMode::TokenBucket, period, 3);
+        // As if the publisher had been idle for 100 periods.
+        pacer.next = pacer.start - period * 100;
+        for _ in 0..3 {
+            assert_eq!(pacer.next_deadline(), None);
+        }
+        assert!(pacer.next_deadline().is_some());
+    }
+
+    #[test]
+    fn poisson_deadlines_have_the_mean_period() {
+        let period = Duration::from_millis(100);
+        let mut pacer = pacer(PacingMode::Poisson, period, 1);
+        assert_eq!(pacer.next_deadline(), None);
+        let msg_num = 10_000;
+        let mut last = pacer.start;
+        for _ in 1..msg_num {
+            let deadline = pacer.next_deadline().unwrap();
+            assert!(deadline >= last);
+            last = deadline;
+        }
+        let mean = (last - pacer.start).as_secs_f64() / (msg_num - 1) as f64;
+        assert!(
+            (mean / period.as_secs_f64() - 1.0).abs() < 0.05,
+            "mean {}",
+            mean
+        );
+    }
+
+    #[test]
+    fn poisson_deadlines_depend_on_the_seed_only() {
+        let period = Duration::from_millis(100);
+        let mut a = pacer(PacingMode::Poisson, period, 1);
+        let mut b = pacer(PacingMode::Poisson, period, 1);
+        b.start = a.start;
+        b.next = a.next;
+        for _ in 0..100 {
+            assert_eq!(a.next_deadline(), b.next_deadline());
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::common::*;
+
+/// The current wall-clock time in nanoseconds since the UNIX epoch.
+/// Publishers and subscribers on different machines need synchronized clocks for this to be comparable.
+pub fn now_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+/// The magic bytes at the start of every message payload.
+pub const MSG_MAGIC: [u8; 4] = *b"ZPRF";
+/// The version of the binary payload format. Bump it whenever the header layout changes.
+pub const MSG_FORMAT_VERSION: u32 = 1;
+/// The size (bytes) of the header placed at the front of each message payload.
+///
+/// Layout (little endian): magic (4) | format version (4) | publisher peer id (8) |
+/// sequence number (8) | send timestamp in ns (8) | payload length (8), followed by the filler.
+pub const MSG_HEADER_SIZE: usize = 40;
+
+/// The bytes used to pad the payload after the header up to the requested payload size.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum PayloadFiller {
+    /// Fill with `.` characters.
+    Dot,
+    /// Fill with zero bytes.
+    Zero,
+    /// Fill with pseudo-random bytes seeded by the publisher peer id (hard to compress).
+    Random,
+}
+
+/// The typed header carried at the front of each message payload.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct MsgHeader {
+    pub version: u32,
+    pub peer_id: u64,
+    pub seq: u64,
+    pub send_nanos: u64,
+    pub payload_len: u64,
+}
+
+impl MsgHeader {
+    pub fn new(peer_id: usize, payload_len: usize) -> Self {
+        MsgHeader {
+            version: MSG_FORMAT_VERSION,
+            peer_id: peer_id as u64,
+            seq: 0,
+            send_nanos: 0,
+            payload_len: payload_len as u64,
+        }
+    }
+
+    /// Writes the header into the first `MSG_HEADER_SIZE` bytes of `buf`.
+    pub fn encode(&self, buf: &mut [u8]) {
+        buf[0..4].copy_from_slice(&MSG_MAGIC);
+        buf[4..8].copy_from_slice(&self.version.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.peer_id.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.seq.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.send_nanos.to_le_bytes());
+        buf[32..40].copy_from_slice(&self.payload_len.to_le_bytes());
+    }
+
+    /// Parses the header from the front of a received payload.
+    pub fn decode(buf: &[u8]) -> Result<Self> {
+        if buf.len() < MSG_HEADER_SIZE {
+            anyhow::bail!(
+                "payload of {} bytes is shorter than the {} bytes header",
+                buf.len(),
+                MSG_HEADER_SIZE
+            );
+        }
+        if buf[0..4] != MSG_MAGIC {
+            anyhow::bail!(
+                "payload does not start with the magic bytes {:?}",
+                MSG_MAGIC
+            );
+        }
+        let read_u64 =
+            |offset: usize| u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap());
+        let version = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+        if version != MSG_FORMAT_VERSION {
+            anyhow::bail!(
+                "unsupported payload format version {} (expected {})",
+                version,
+                MSG_FORMAT_VERSION
+            );
+        }
+        let header = MsgHeader {
+            version,
+            peer_id: read_u64(8),
+            seq: read_u64(16),
+            send_nanos: read_u64(24),
+            payload_len: read_u64(32),
+        };
+        if header.payload_len != buf.len() as u64 {
+            anyhow::bail!(
+                "payload length {} does not match the length {} in the header",
+                buf.len(),
+                header.payload_len
+            );
+        }
+        Ok(header)
+    }
+
+    /// The one-way latency (ns) of the message received at `recv_nanos`.
+    pub fn latency(&self, recv_nanos: u64) -> u64 {
+        recv_nanos.saturating_sub(self.send_nanos)
+    }
+}
+
+pub fn get_msg_payload(args_payload_size: usize, peer_id: usize, filler: PayloadFiller) -> Vec<u8> {
+    let payload_size = if args_payload_size < MSG_HEADER_SIZE {
+        warn!(
+            "Payload size cannot be less than {} bytes, using {} bytes for current test.",
+            MSG_HEADER_SIZE, MSG_HEADER_SIZE
+        );
+        MSG_HEADER_SIZE
+    } else {
+        args_payload_size
+    };
+    let mut msg_payload = vec![0; payload_size];
+    MsgHeader::new(peer_id, payload_size).encode(&mut msg_payload);
+    match filler {
+        PayloadFiller::Dot => msg_payload[MSG_HEADER_SIZE..].fill(b'.'),
+        PayloadFiller::Zero => {}
+        PayloadFiller::Random => {
+            // xorshift64, seeded so that every publisher sends a different but reproducible filler
+            let mut state = peer_id as u64 + 0x9E37_79B9_7F4A_7C15;
+            for byte in msg_payload[MSG_HEADER_SIZE..].iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte = state as u8;
+            }
+        }
+    }
+    assert!(msg_payload.len() == payload_size);
+    msg_payload
+}
+
+/// Writes the sequence number and the current send timestamp into the header of the payload
+/// generated by `get_msg_payload`.
+pub fn stamp_msg_payload(msg_payload: &mut [u8], msg_id: usize) {
+    msg_payload[16..24].copy_from_slice(&(msg_id as u64).to_le_bytes());
+    msg_payload[24..32].copy_from_slice(&now_nanos().to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamped_payload_decodes() {
+        let mut payload = get_msg_payload(64, 7, PayloadFiller::Dot);
+        stamp_msg_payload(&mut payload, 42);
+        let header = MsgHeader::decode(&payload).unwrap();
+        assert_eq!(header.version, MSG_FORMAT_VERSION);
+        assert_eq!(header.peer_id, 7);
+        assert_eq!(header.seq, 42);
+        assert_eq!(header.payload_len, 64);
+        assert!(header.send_nanos > 0);
+        assert!(payload[MSG_HEADER_SIZE..].iter().all(|byte| *byte == b'.'));
+    }
+
+    #[test]
+    fn payload_is_at_least_the_header() {
+        let payload = get_msg_payload(10, 0, PayloadFiller::Zero);
+        assert_eq!(payload.len(), MSG_HEADER_SIZE);
+        assert!(MsgHeader::decode(&payload).is_ok());
+    }
+
+    #[test]
+    fn random_filler_depends_on_the_peer_id() {
+        let filler = |peer_id| {
+            get_msg_payload(128, peer_id, PayloadFiller::Random)[MSG_HEADER_SIZE..].to_vec()
+        };
+        assert_eq!(filler(1), filler(1));
+        assert_ne!(filler(1), filler(2));
+    }
+
+    #[test]
+    fn decode_rejects_short_payloads() {
+        let payload = get_msg_payload(64, 0, PayloadFiller::Dot);
+        assert!(MsgHeader::decode(&payload[..MSG_HEADER_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_bad_magic() {
+        let mut payload = get_msg_payload(64, 0, PayloadFiller::Dot);
+        payload[0] = b'X';
+        assert!(MsgHeader::decode(&payload).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_other_versions() {
+        let mut payload = get_msg_payload(64, 0, PayloadFiller::Dot);
+        payload[4..8].copy_from_slice(&(MSG_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(MsgHeader::decode(&payload).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_mismatched_lengths() {
+        let payload = get_msg_payload(64, 0, PayloadFiller::Dot);
+        assert!(MsgHeader::decode(&payload[..63]).is_err());
+        let mut longer = payload.clone();
+        longer.push(b'.');
+        assert!(MsgHeader::decode(&longer).is_err());
+    }
+
+    #[test]
+    fn latency_saturates_on_clock_skew() {
+        let header = MsgHeader {
+            send_nanos: 1_000,
+            ..MsgHeader::new(0, MSG_HEADER_SIZE)
+        };
+        assert_eq!(header.latency(1_500), 500);
+        assert_eq!(header.latency(500), 0);
+    }
+}
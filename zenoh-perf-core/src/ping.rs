@@ -0,0 +1,335 @@
+use crate::{
+    common::*,
+    config::{SessionRole, WorkerConfig},
+    histogram::LatencyHistogram,
+    pacing::Pacer,
+    payload::{get_msg_payload, now_nanos, stamp_msg_payload, MsgHeader},
+    result::{PubTimeStatus, SubTimeStatus},
+    stats::LatencyStats,
+    throughput::ThroughputMeter,
+    workers::SubReport,
+};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// The key expression pinger `peer_id` sends its pings on.
+pub fn ping_key_expr(peer_id: usize) -> String {
+    format!("/ping/{}", peer_id)
+}
+
+/// The key expression the pongs to pinger `peer_id` are sent on.
+pub fn pong_key_expr(peer_id: usize) -> String {
+    format!("/pong/{}", peer_id)
+}
+
+/// The round-trip times measured by a pinger with one ponger.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PongerRtt {
+    pub ponger_id: usize,
+    pub pong_num: usize,
+    pub rtt: Option<LatencyStats>,
+    /// Unit: nanoseconds (ns).
+    pub rtt_histogram: LatencyHistogram,
+}
+
+/// The result of a pinger, written into `ping_{peer_id}_info_{exp}.json`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PingResult {
+    pub peer_id: usize,
+    pub ping_num: usize,
+    /// The pings not answered by every ponger before the reply timeout.
+    pub timed_out_ping_num: usize,
+    /// The round-trip times with all the pongers together.
+    pub rtt: Option<LatencyStats>,
+    /// Unit: nanoseconds (ns).
+    pub rtt_histogram: LatencyHistogram,
+    pub per_ponger: Vec<PongerRtt>,
+}
+
+/// The ping-pong counterpart of `publish_worker`: echoes the pings of every pinger back on the
+/// pong key of the pinger until the round times out. The echoed payload carries the id of the
+/// ponger instead of the one of the pinger, the other header fields are left untouched.
+pub async fn pong_worker(
+    zenoh: Arc<Session>,
+    peer_id: usize,
+    config: WorkerConfig,
+    session_start_time: Option<Instant>,
+    pub_sub_worker_start: Option<Instant>,
+) -> Result<()> {
+    let start = config.start;
+    let timeout = config.timeout();
+    let start_worker = Instant::now() - start;
+    let mut session_start = session_start_time;
+    let zenoh_new = if config.multipeer_mode {
+        let zenoh_new = config
+            .open_worker_session(SessionRole::Publisher(peer_id))
+            .await;
+        session_start = Some(Instant::now());
+        Some(zenoh_new)
+    } else {
+        None
+    };
+    let before_sending;
+    let start_sending;
+    let mut pong_num = 0;
+    {
+        let session = zenoh_new.as_ref().unwrap_or(&*zenoh);
+        let mut subscriber = session.subscribe("/ping/**").await.unwrap();
+        before_sending = Instant::now() - start;
+        start_sending = Instant::now() - start;
+        info!("start answering pings");
+        let round_end = async_std::task::sleep(timeout.saturating_duration_since(Instant::now()));
+        futures::pin_mut!(round_end);
+        let mut pings = subscriber.receiver().take_until(round_end);
+        while let Some(sample) = pings.next().await {
+            let mut payload = sample.value.payload.contiguous().to_vec();
+            let mut header = match MsgHeader::decode(&payload) {
+                Ok(header) => header,
+                Err(err) => {
+                    debug!("Received a malformed ping: {}", err);
+                    continue;
+                }
+            };
+            let pinger_id = header.peer_id as usize;
+            header.peer_id = peer_id as u64;
+            header.encode(&mut payload);
+            session
+                .put(pong_key_expr(pinger_id).as_str(), payload)
+                .await
+                .unwrap();
+            pong_num += 1;
+        }
+    }
+    let after_sending = Instant::now() - start;
+    if let Some(zenoh_new) = zenoh_new {
+        zenoh_new.close().await.unwrap();
+    }
+
+    let file_path =
+        config
+            .output_dir
+            .join(format!("put_{}_info_{}.json", peer_id, config.exp_name()));
+    let pub_time_status = PubTimeStatus {
+        start_pub_worker: start_worker.as_millis(),
+        session_start: session_start.map(|time| (time - start).as_millis()),
+        pub_sub_worker_start: pub_sub_worker_start.map(|time| (time - start).as_millis()),
+        before_sending: before_sending.as_millis(),
+        start_sending: start_sending.as_millis(),
+        after_sending: after_sending.as_millis(),
+        sent_msg_num: pong_num,
+    };
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&pub_time_status).unwrap()
+    )
+    .unwrap();
+
+    Ok(())
+}
+
+/// The ping-pong counterpart of `subscribe_worker`: sends `num_msgs_per_peer` pings (or pings
+/// until the end of the sending period in throughput mode) and waits for the pong of every ponger
+/// before sending the next one.
+///
+/// The round-trip times are measured with the local monotonic clock only, so unlike the one-way
+/// latencies they do not depend on the clocks of the machines being synchronized. Ping number `n`
+/// answered by ponger `p` is tracked as message `n` of publisher `p` in the report.
+pub async fn ping_worker(
+    zenoh: Arc<Session>,
+    peer_id: usize,
+    tx: flume::Sender<SubReport>,
+    config: WorkerConfig,
+    session_start_time: Option<Instant>,
+    pub_sub_worker_start: Option<Instant>,
+) -> Result<()> {
+    let start = config.start;
+    let start_until = config.start_until();
+    let reply_timeout = Duration::from_millis(config.reply_timeout);
+    let expected_pong_num = config.total_put_number + config.remote_pub_peers;
+    let start_worker = Instant::now() - start;
+    let msg_payload = get_msg_payload(config.payload_size, peer_id, config.payload_filler);
+    let mut report = SubReport::new(
+        peer_id,
+        ThroughputMeter::new(
+            now_nanos()
+                + start_until
+                    .saturating_duration_since(Instant::now())
+                    .as_nanos() as u64,
+        ),
+    );
+    let mut ponger_rtts: BTreeMap<usize, LatencyHistogram> = BTreeMap::new();
+    let mut session_start = session_start_time;
+    let zenoh_new = if config.multipeer_mode {
+        let zenoh_new = config
+            .open_worker_session(SessionRole::Subscriber(peer_id))
+            .await;
+        session_start = Some(Instant::now());
+        Some(zenoh_new)
+    } else {
+        None
+    };
+    let after_subscribing;
+    let start_receiving;
+    let mut timed_out_ping_num = 0;
+    let mut ping_sent: Vec<Instant> = vec![];
+    {
+        let session = zenoh_new.as_ref().unwrap_or(&*zenoh);
+        let mut subscriber = session.subscribe(pong_key_expr(peer_id)).await.unwrap();
+        after_subscribing = Instant::now() - start;
+        if start_until > Instant::now() {
+            async_std::task::sleep(start_until - Instant::now()).await;
+        }
+        start_receiving = Instant::now() - start;
+        let ping_key = ping_key_expr(peer_id);
+        let pongs = subscriber.receiver();
+        let mut pacer = Pacer::new(&config, peer_id)?;
+        loop {
+            let seq = ping_sent.len();
+            let deadline = pacer.next_deadline();
+            if config.is_throughput_mode() {
+                if deadline.unwrap_or_else(Instant::now) >= config.send_until() {
+                    break;
+                }
+            } else if seq >= config.num_msgs_per_peer {
+                break;
+            }
+            if let Some(deadline) = deadline {
+                pacer.sleep_until(deadline).await;
+            }
+            let mut payload = msg_payload.clone();
+            stamp_msg_payload(&mut payload, seq);
+            let sent = Instant::now();
+            ping_sent.push(sent);
+            session.put(&ping_key, payload).await.unwrap();
+
+            let reply_deadline = sent + reply_timeout;
+            let mut pong_num = 0;
+            while pong_num < expected_pong_num {
+                let remaining = reply_deadline.saturating_duration_since(Instant::now());
+                let sample = match async_std::future::timeout(remaining, pongs.next()).await {
+                    Ok(Some(sample)) => sample,
+                    Ok(None) => break,
+                    Err(_) => {
+                        timed_out_ping_num += 1;
+                        break;
+                    }
+                };
+                let recv_instant = Instant::now();
+                let recv_nanos = now_nanos();
+                let mut unique = false;
+                match MsgHeader::decode(&sample.value.payload.contiguous()) {
+                    // Late pongs of the previous pings are still accounted for.
+                    Ok(header) if (header.seq as usize) < ping_sent.len() => {
+                        if header.seq as usize == seq {
+                            pong_num += 1;
+                        }
+                        let ponger_id = header.peer_id as usize;
+                        unique = report
+                            .seq_trackers
+                            .entry(ponger_id)
+                            .or_default()
+                            .record(header.seq);
+                        if unique {
+                            let rtt = (recv_instant - ping_sent[header.seq as usize]).as_nanos();
+                            report.latency.record(rtt as u64);
+                            ponger_rtts.entry(ponger_id).or_default().record(rtt as u64);
+                        }
+                    }
+                    Ok(header) => debug!("Received a pong to unknown ping {}", header.seq),
+                    Err(err) => debug!("Received a malformed pong: {}", err),
+                }
+                report.record_sample(recv_nanos, sample.value.payload.len(), unique);
+            }
+        }
+    }
+    let after_receiving = Instant::now() - start;
+    if timed_out_ping_num > 0 {
+        warn!(
+            "pinger {}: {}/{} pings timed out after {} ms",
+            peer_id,
+            timed_out_ping_num,
+            ping_sent.len(),
+            config.reply_timeout
+        );
+    }
+    report.timed_out_request_num = Some(timed_out_ping_num);
+
+    let ping_result = PingResult {
+        peer_id,
+        ping_num: ping_sent.len(),
+        timed_out_ping_num,
+        rtt: LatencyStats::from_histogram(&report.latency),
+        rtt_histogram: report.latency.clone(),
+        per_ponger: ponger_rtts
+            .iter()
+            .map(|(ponger_id, rtts)| PongerRtt {
+                ponger_id: *ponger_id,
+                pong_num: rtts.count as usize,
+                rtt: LatencyStats::from_histogram(rtts),
+                rtt_histogram: rtts.clone(),
+            })
+            .collect(),
+    };
+    tx.send_async(report).await.unwrap();
+    if let Some(zenoh_new) = zenoh_new {
+        zenoh_new.close().await.unwrap();
+    }
+
+    let file_path =
+        config
+            .output_dir
+            .join(format!("ping_{}_info_{}.json", peer_id, config.exp_name()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&ping_result).unwrap()
+    )
+    .unwrap();
+
+    let sub_time_status = SubTimeStatus {
+        process_start_sec: config
+            .process_start
+            .map(|time| time.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64),
+        process_start_millis: config
+            .process_start
+            .map(|time| time.duration_since(UNIX_EPOCH).unwrap().subsec_millis() as i16),
+        start_sub_worker: start_worker.as_millis(),
+        session_start: session_start.map(|time| (time - start).as_millis()),
+        pub_sub_worker_start: pub_sub_worker_start.map(|time| (time - start).as_millis()),
+        after_subscribing: after_subscribing.as_millis(),
+        start_receiving: start_receiving.as_millis(),
+        after_receiving: after_receiving.as_millis(),
+    };
+    let file_path =
+        config
+            .output_dir
+            .join(format!("sub_{}_info_{}.json", peer_id, config.exp_name()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&sub_time_status).unwrap()
+    )
+    .unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use crate::{
+    common::*,
+    histogram::LatencyHistogram,
+    stats::{receive_rate, LatencyStats, PubSubStats},
+};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The class of the publishers outside of the groups.
+pub const DEFAULT_CLASS: &str = "default";
+
+/// What a publisher does when the queues of the session are full.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CongestionMode {
+    /// Wait until the message can be queued.
+    Block,
+    /// Drop the message.
+    Drop,
+}
+
+impl CongestionMode {
+    pub fn congestion_control(&self) -> CongestionControl {
+        match self {
+            CongestionMode::Block => CongestionControl::Block,
+            CongestionMode::Drop => CongestionControl::Drop,
+        }
+    }
+}
+
+/// The zenoh priorities, from the highest to the lowest.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriorityClass {
+    RealTime,
+    InteractiveHigh,
+    InteractiveLow,
+    DataHigh,
+    Data,
+    DataLow,
+    Background,
+}
+
+impl PriorityClass {
+    pub fn priority(&self) -> Priority {
+        match self {
+            PriorityClass::RealTime => Priority::RealTime,
+            PriorityClass::InteractiveHigh => Priority::InteractiveHigh,
+            PriorityClass::InteractiveLow => Priority::InteractiveLow,
+            PriorityClass::DataHigh => Priority::DataHigh,
+            PriorityClass::Data => Priority::Data,
+            PriorityClass::DataLow => Priority::DataLow,
+            PriorityClass::Background => Priority::Background,
+        }
+    }
+}
+
+/// A group of publishers sharing the same QoS, written `name:count:priority:congestion[:rate]`,
+/// e.g. `alarm:1:real-time:block:100`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PublisherGroup {
+    pub name: String,
+    /// The number of publishers in the group.
+    pub pub_num: usize,
+    pub priority: PriorityClass,
+    pub congestion_control: CongestionMode,
+    /// The rate (messages/s) each publisher of the group sends at, with a constant pacing.
+    /// The group follows the pacing of the experiment if not set.
+    pub pub_rate: Option<f64>,
+}
+
+fn parse_arg_enum<T: clap::ArgEnum>(value: &str) -> Result<T> {
+    T::from_str(value, true).map_err(|err| anyhow::anyhow!(err))
+}
+
+impl FromStr for PublisherGroup {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let fields = text.split(':').collect::<Vec<_>>();
+        let (name, pub_num, priority, congestion_control, pub_rate) = match fields[..] {
+            [name, pub_num, priority, congestion_control] => {
+                (name, pub_num, priority, congestion_control, None)
+            }
+            [name, pub_num, priority, congestion_control, pub_rate] => {
+                (name, pub_num, priority, congestion_control, Some(pub_rate))
+            }
+            _ => anyhow::bail!(
+                "expected name:count:priority:congestion[:rate], got {:?}",
+                text
+            ),
+        };
+        if name.is_empty() || name == DEFAULT_CLASS {
+            anyhow::bail!("invalid publisher group name {:?}", name);
+        }
+        let pub_rate = pub_rate.map(|rate| rate.parse::<f64>()).transpose()?;
+        if matches!(pub_rate, Some(rate) if rate <= 0.0) {
+            anyhow::bail!("the rate of the publisher group {} must be positive", name);
+        }
+        Ok(PublisherGroup {
+            name: name.to_string(),
+            pub_num: pub_num.parse()?,
+            priority: parse_arg_enum(priority)?,
+            congestion_control: parse_arg_enum(congestion_control)?,
+            pub_rate,
+        })
+    }
+}
+
+/// The QoS of the publishers: the first `groups[0].pub_num` publishers (by peer id) form the first
+/// group, the next ones the second group, and so on. The publishers after the groups use the
+/// default priority and congestion control.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PublisherQos {
+    pub groups: Vec<PublisherGroup>,
+    pub priority: PriorityClass,
+    pub congestion_control: CongestionMode,
+}
+
+impl Default for PublisherQos {
+    /// The QoS of `put` when none is given.
+    fn default() -> Self {
+        PublisherQos {
+            groups: vec![],
+            priority: PriorityClass::Data,
+            congestion_control: CongestionMode::Drop,
+        }
+    }
+}
+
+/// The delivery statistics of the messages of a class of publishers, over all the subscribers.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClassStats {
+    pub class: String,
+    pub priority: PriorityClass,
+    pub congestion_control: CongestionMode,
+    pub pub_num: usize,
+    pub recvd_msg_num: usize,
+    pub expected_msg_num: usize,
+    /// The messages of the class the subscribers did not receive, dropped by congestion control
+    /// or lost.
+    pub lost_msg_num: usize,
+    pub receive_rate: f64,
+    pub latency: Option<LatencyStats>,
+    /// Unit: nanoseconds (ns).
+    pub latency_histogram: LatencyHistogram,
+}
+
+impl PublisherQos {
+    /// The group of publisher `pub_peer_id`, `None` if it comes after the groups.
+    pub fn group(&self, pub_peer_id: usize) -> Option<&PublisherGroup> {
+        let mut first = 0;
+        for group in self.groups.iter() {
+            if pub_peer_id < first + group.pub_num {
+                return Some(group);
+            }
+            first += group.pub_num;
+        }
+        None
+    }
+
+    /// The class the messages of publisher `pub_peer_id` are accounted in, only tracked when
+    /// there are groups.
+    pub fn class(&self, pub_peer_id: usize) -> Option<&str> {
+        if self.groups.is_empty() {
+            return None;
+        }
+        Some(
+            self.group(pub_peer_id)
+                .map_or(DEFAULT_CLASS, |group| group.name.as_str()),
+        )
+    }
+
+    pub fn priority(&self, pub_peer_id: usize) -> Priority {
+        self.group(pub_peer_id)
+            .map_or(self.priority, |group| group.priority)
+            .priority()
+    }
+
+    pub fn congestion_control(&self, pub_peer_id: usize) -> CongestionControl {
+        self.group(pub_peer_id)
+            .map_or(self.congestion_control, |group| group.congestion_control)
+            .congestion_control()
+    }
+
+    /// The statistics of every class, from the (publisher, subscriber) matrix of the experiment
+    /// and the latencies the subscribers recorded by class. Empty without groups.
+    ///
+    /// `sent_msg_nums` has the number of messages each publisher sent, in throughput mode: every
+    /// subscriber of a publisher is then expected to receive them all, rather than the span of
+    /// sequence numbers it received, which hides the messages dropped at the end of the round.
+    /// The publishers missing from it are expected as in the matrix.
+    pub fn class_stats(
+        &self,
+        pub_peer_ids: impl IntoIterator<Item = usize>,
+        pub_sub_matrix: &[PubSubStats],
+        class_latency: &BTreeMap<String, LatencyHistogram>,
+        sent_msg_nums: &BTreeMap<usize, usize>,
+    ) -> Vec<ClassStats> {
+        if self.groups.is_empty() {
+            return vec![];
+        }
+        let mut classes = self
+            .groups
+            .iter()
+            .map(|group| {
+                (
+                    group.name.as_str(),
+                    group.priority,
+                    group.congestion_control,
+                )
+            })
+            .collect::<Vec<_>>();
+        let pub_peer_ids = pub_peer_ids.into_iter().collect::<Vec<_>>();
+        if pub_peer_ids.iter().any(|id| self.group(*id).is_none()) {
+            classes.push((DEFAULT_CLASS, self.priority, self.congestion_control));
+        }
+        classes
+            .into_iter()
+            .map(|(class, priority, congestion_control)| {
+                let in_class = |pub_peer_id: usize| self.class(pub_peer_id) == Some(class);
+                let stats = pub_sub_matrix
+                    .iter()
+                    .filter(|stats| in_class(stats.pub_peer_id))
+                    .collect::<Vec<_>>();
+                let expected = |stats: &PubSubStats| {
+                    sent_msg_nums
+                        .get(&stats.pub_peer_id)
+                        .copied()
+                        .unwrap_or(stats.expected_msg_num)
+                };
+                let recvd_msg_num = stats.iter().map(|stats| stats.recvd_msg_num).sum();
+                let expected_msg_num = stats.iter().map(|stats| expected(stats)).sum();
+                let latency_histogram = class_latency
+                    .get(class)
+                    .cloned()
+                    .unwrap_or_else(LatencyHistogram::new);
+                ClassStats {
+                    class: class.to_string(),
+                    priority,
+                    congestion_control,
+                    pub_num: pub_peer_ids.iter().filter(|id| in_class(**id)).count(),
+                    recvd_msg_num,
+                    expected_msg_num,
+                    lost_msg_num: stats
+                        .iter()
+                        .map(|stats| expected(stats).saturating_sub(stats.recvd_msg_num))
+                        .sum(),
+                    receive_rate: receive_rate(recvd_msg_num, expected_msg_num),
+                    latency: LatencyStats::from_histogram(&latency_histogram),
+                    latency_histogram,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(pub_peer_id: usize, sub_peer_id: usize, recvd: usize, expected: usize) -> PubSubStats {
+        PubSubStats {
+            pub_peer_id,
+            sub_peer_id,
+            recvd_msg_num: recvd,
+            expected_msg_num: expected,
+            lost_msg_num: expected.saturating_sub(recvd),
+            duplicated_msg_num: 0,
+            out_of_order_msg_num: 0,
+            rejected_msg_num: 0,
+        }
+    }
+
+    fn qos() -> PublisherQos {
+        PublisherQos {
+            groups: vec!["control:1:real-time:block".parse().unwrap()],
+            ..PublisherQos::default()
+        }
+    }
+
+    #[test]
+    fn publisher_groups() {
+        let group: PublisherGroup = "bulk:2:background:drop:500".parse().unwrap();
+        assert_eq!(group.pub_num, 2);
+        assert_eq!(group.pub_rate, Some(500.0));
+        assert!("default:1:data:drop".parse::<PublisherGroup>().is_err());
+        assert!("bulk:1:data".parse::<PublisherGroup>().is_err());
+        let qos = qos();
+        assert_eq!(qos.class(0), Some("control"));
+        assert_eq!(qos.class(1), Some(DEFAULT_CLASS));
+        assert_eq!(PublisherQos::default().class(0), None);
+    }
+
+    #[test]
+    fn class_stats_expect_the_sent_messages() {
+        // Subscribers 0 and 1 both hear from publishers 0 and 1, the end of the round of
+        // publisher 1 being dropped: the sequence numbers received only span 60 of the 100 sent.
+        let matrix = [
+            pair(0, 0, 10, 10),
+            pair(1, 0, 50, 60),
+            pair(0, 1, 10, 10),
+            pair(1, 1, 60, 60),
+        ];
+        let sent_msg_nums = [(0, 10), (1, 100)].into_iter().collect();
+        let stats = qos().class_stats([0, 1], &matrix, &BTreeMap::new(), &sent_msg_nums);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].class, "control");
+        assert_eq!((stats[0].expected_msg_num, stats[0].lost_msg_num), (20, 0));
+        assert_eq!(stats[1].class, DEFAULT_CLASS);
+        assert_eq!(stats[1].recvd_msg_num, 110);
+        assert_eq!(
+            (stats[1].expected_msg_num, stats[1].lost_msg_num),
+            (200, 90)
+        );
+        assert_eq!(stats[1].receive_rate, 0.55);
+
+        // Without the sent messages, the matrix is used.
+        let stats = qos().class_stats([0, 1], &matrix, &BTreeMap::new(), &BTreeMap::new());
+        assert_eq!(
+            (stats[1].expected_msg_num, stats[1].lost_msg_num),
+            (120, 10)
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    common::*,
+    config::{SessionRole, WorkerConfig},
+    pacing::Pacer,
+    payload::{get_msg_payload, now_nanos, stamp_msg_payload, MsgHeader},
+    result::{PubTimeStatus, SubTimeStatus},
+    throughput::ThroughputMeter,
+    workers::SubReport,
+};
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// The key expression the queryable of responder `peer_id` is declared on.
+pub fn responder_key_expr(peer_id: usize) -> String {
+    format!("/demo/query/{}", peer_id)
+}
+
+/// The selector queriers send their queries to, matching every responder.
+pub const QUERY_SELECTOR: &str = "/demo/query/**";
+
+/// The request/reply counterpart of `publish_worker`: declares a queryable and answers every
+/// query with a `payload_size` bytes payload until the round times out.
+pub async fn queryable_worker(
+    zenoh: Arc<Session>,
+    peer_id: usize,
+    config: WorkerConfig,
+    session_start_time: Option<Instant>,
+    pub_sub_worker_start: Option<Instant>,
+) -> Result<()> {
+    let start = config.start;
+    let timeout = config.timeout();
+    let start_worker = Instant::now() - start;
+    let msg_payload = get_msg_payload(config.payload_size, peer_id, config.payload_filler);
+    let key_expr = responder_key_expr(peer_id);
+    let mut session_start = session_start_time;
+    let zenoh_new = if config.multipeer_mode {
+        let zenoh_new = config
+            .open_worker_session(SessionRole::Publisher(peer_id))
+            .await;
+        session_start = Some(Instant::now());
+        Some(zenoh_new)
+    } else {
+        None
+    };
+    let before_sending;
+    let start_sending;
+    let mut reply_num = 0;
+    {
+        let session = zenoh_new.as_ref().unwrap_or(&*zenoh);
+        let mut queryable = session.queryable(&key_expr).await.unwrap();
+        before_sending = Instant::now() - start;
+        start_sending = Instant::now() - start;
+        info!("start replying to queries");
+        let round_end = async_std::task::sleep(timeout.saturating_duration_since(Instant::now()));
+        futures::pin_mut!(round_end);
+        let mut queries = queryable.receiver().take_until(round_end);
+        while let Some(query) = queries.next().await {
+            let mut payload = msg_payload.clone();
+            stamp_msg_payload(&mut payload, reply_num);
+            query
+                .reply_async(Sample::new(key_expr.clone(), payload))
+                .await;
+            reply_num += 1;
+        }
+    }
+    let after_sending = Instant::now() - start;
+    if let Some(zenoh_new) = zenoh_new {
+        zenoh_new.close().await.unwrap();
+    }
+
+    let file_path =
+        config
+            .output_dir
+            .join(format!("put_{}_info_{}.json", peer_id, config.exp_name()));
+    let pub_time_status = PubTimeStatus {
+        start_pub_worker: start_worker.as_millis(),
+        session_start: session_start.map(|time| (time - start).as_millis()),
+        pub_sub_worker_start: pub_sub_worker_start.map(|time| (time - start).as_millis()),
+        before_sending: before_sending.as_millis(),
+        start_sending: start_sending.as_millis(),
+        after_sending: after_sending.as_millis(),
+        sent_msg_num: reply_num,
+    };
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&pub_time_status).unwrap()
+    )
+    .unwrap();
+
+    Ok(())
+}
+
+/// The request/reply counterpart of `subscribe_worker`: issues `num_msgs_per_peer` queries (or
+/// queries until the end of the sending period in throughput mode), paced like the publishers.
+/// The querier stops at the end of the round, when the queryables stop answering: the queries it
+/// could not send are counted as lost, and a reply cut short by the end of the round does not
+/// count as timed out.
+///
+/// The report has the same shape as the one of a subscriber: the latencies are the round-trip
+/// times of the replies, and query number `n` answered by responder `r` is tracked as message
+/// `n` of publisher `r`, so that the missing replies show up as lost messages.
+pub async fn query_worker(
+    zenoh: Arc<Session>,
+    peer_id: usize,
+    tx: flume::Sender<SubReport>,
+    config: WorkerConfig,
+    session_start_time: Option<Instant>,
+    pub_sub_worker_start: Option<Instant>,
+) -> Result<()> {
+    let start = config.start;
+    let start_until = config.start_until();
+    let timeout = config.timeout();
+    let reply_timeout = Duration::from_millis(config.reply_timeout);
+    let start_worker = Instant::now() - start;
+    let mut report = SubReport::new(
+        peer_id,
+        ThroughputMeter::new(
+            now_nanos()
+                + start_until
+                    .saturating_duration_since(Instant::now())
+                    .as_nanos() as u64,
+        ),
+    );
+    let mut session_start = session_start_time;
+    let zenoh_new = if config.multipeer_mode {
+        let zenoh_new = config
+            .open_worker_session(SessionRole::Subscriber(peer_id))
+            .await;
+        session_start = Some(Instant::now());
+        Some(zenoh_new)
+    } else {
+        None
+    };
+    let session = zenoh_new.as_ref().unwrap_or(&*zenoh);
+    let after_subscribing = Instant::now() - start;
+    if start_until > Instant::now() {
+        async_std::task::sleep(start_until - Instant::now()).await;
+    }
+    let start_receiving = Instant::now() - start;
+    let mut pacer = Pacer::new(&config, peer_id)?;
+    let mut timed_out_request_num = 0;
+    let mut seq = 0;
+    loop {
+        let deadline = pacer.next_deadline();
+        if config.is_throughput_mode() {
+            let send_until = config.send_until();
+            if deadline.unwrap_or_else(Instant::now) >= send_until {
+                break;
+            }
+        } else if seq >= config.num_msgs_per_peer {
+            break;
+        }
+        if deadline.unwrap_or_else(Instant::now) >= timeout {
+            warn!(
+                "querier {}: the round ended after {} queries. Please increase round_timeout.",
+                peer_id, seq
+            );
+            break;
+        }
+        if let Some(deadline) = deadline {
+            pacer.sleep_until(deadline).await;
+        }
+        let sent = Instant::now();
+        let mut replies = session.get(QUERY_SELECTOR).await.unwrap();
+        let reply_deadline = (sent + reply_timeout).min(timeout);
+        loop {
+            let remaining = reply_deadline.saturating_duration_since(Instant::now());
+            match async_std::future::timeout(remaining, replies.next()).await {
+                Ok(Some(reply)) => {
+                    let rtt = Instant::now() - sent;
+                    let recv_nanos = now_nanos();
+                    let sample = reply.data;
+                    let mut unique = false;
+                    match MsgHeader::decode(&sample.value.payload.contiguous()) {
+                        Ok(header) => {
+                            report.latency.record(rtt.as_nanos() as u64);
+                            unique = report
+                                .seq_trackers
+                                .entry(header.peer_id as usize)
+                                .or_default()
+                                .record(seq as u64);
+                        }
+                        Err(err) => debug!("Received a malformed reply: {}", err),
+                    }
+                    report.record_sample(recv_nanos, sample.value.payload.len(), unique);
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    if reply_deadline < timeout {
+                        timed_out_request_num += 1;
+                    }
+                    break;
+                }
+            }
+        }
+        seq += 1;
+    }
+    let after_receiving = Instant::now() - start;
+    if timed_out_request_num > 0 {
+        warn!(
+            "querier {}: {}/{} queries timed out after {} ms",
+            peer_id, timed_out_request_num, seq, config.reply_timeout
+        );
+    }
+    report.timed_out_request_num = Some(timed_out_request_num);
+    tx.send_async(report).await.unwrap();
+    if let Some(zenoh_new) = zenoh_new {
+        zenoh_new.close().await.unwrap();
+    }
+
+    let sub_time_status = SubTimeStatus {
+        process_start_sec: config
+            .process_start
+            .map(|time| time.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64),
+        process_start_millis: config
+            .process_start
+            .map(|time| time.duration_since(UNIX_EPOCH).unwrap().subsec_millis() as i16),
+        start_sub_worker: start_worker.as_millis(),
+        session_start: session_start.map(|time| (time - start).as_millis()),
+        pub_sub_worker_start: pub_sub_worker_start.map(|time| (time - start).as_millis()),
+        after_subscribing: after_subscribing.as_millis(),
+        start_receiving: start_receiving.as_millis(),
+        after_receiving: after_receiving.as_millis(),
+    };
+    let file_path =
+        config
+            .output_dir
+            .join(format!("sub_{}_info_{}.json", peer_id, config.exp_name()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&sub_time_status).unwrap()
+    )
+    .unwrap();
+
+    Ok(())
+}
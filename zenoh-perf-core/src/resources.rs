@@ -0,0 +1,125 @@
+use crate::common::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The unit of the CPU times in `/proc/<pid>/stat`, fixed to 100 Hz by the kernel ABI whatever the
+/// kernel tick rate.
+const USER_HZ: f64 = 100.0;
+
+/// The resource usage of the process at one point of the round.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ResourceSample {
+    /// The time of the sample since the start of the round. Unit: milliseconds (ms).
+    pub time: u128,
+    /// The CPU usage of the process since the previous sample, 100% being one core fully used.
+    pub cpu_percent: f64,
+    /// The resident set size. Unit: bytes.
+    pub rss: u64,
+    /// The virtual memory size. Unit: bytes.
+    pub vms: u64,
+    pub thread_num: u64,
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+}
+
+/// The raw counters read from `/proc/self`.
+struct ProcCounters {
+    /// The user and system CPU time. Unit: 1/USER_HZ s.
+    cpu_ticks: u64,
+    rss: u64,
+    vms: u64,
+    thread_num: u64,
+    voluntary_ctxt_switches: u64,
+    nonvoluntary_ctxt_switches: u64,
+}
+
+/// Reads the counters of the current process, only available on Linux.
+fn read_proc_counters() -> Result<ProcCounters> {
+    let stat = std::fs::read_to_string("/proc/self/stat")?;
+    // The command name may contain spaces, the fields are counted from the closing parenthesis,
+    // `fields[0]` being the process state (field 3 in proc(5)).
+    let fields = stat
+        .rsplit_once(')')
+        .map(|(_, fields)| fields.split_whitespace().collect::<Vec<_>>())
+        .unwrap_or_default();
+    if fields.len() < 13 {
+        anyhow::bail!("unexpected /proc/self/stat format: {}", stat);
+    }
+    let utime: u64 = fields[11].parse()?;
+    let stime: u64 = fields[12].parse()?;
+
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    let field = |name: &str| -> Result<u64> {
+        let line = status
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .ok_or_else(|| anyhow::anyhow!("{} missing from /proc/self/status", name))?;
+        Ok(line.trim().trim_end_matches("kB").trim().parse()?)
+    };
+    Ok(ProcCounters {
+        cpu_ticks: utime + stime,
+        rss: field("VmRSS")? * 1024,
+        vms: field("VmSize")? * 1024,
+        thread_num: field("Threads")?,
+        voluntary_ctxt_switches: field("voluntary_ctxt_switches")?,
+        nonvoluntary_ctxt_switches: field("nonvoluntary_ctxt_switches")?,
+    })
+}
+
+/// Samples the CPU and memory usage of the process in the background, alongside the workers.
+pub struct ResourceSampler {
+    stop: Arc<AtomicBool>,
+    handle: async_std::task::JoinHandle<Vec<ResourceSample>>,
+}
+
+impl ResourceSampler {
+    /// Starts sampling every `interval`, the times of the samples being relative to `start`.
+    /// The interval is at least 1 ms, so that a zero interval does not busy-read `/proc`.
+    pub fn spawn(start: Instant, interval: Duration) -> Self {
+        let interval = interval.max(Duration::from_millis(1));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = async_std::task::spawn({
+            let stop = stop.clone();
+            async move {
+                let mut samples = vec![];
+                let mut previous: Option<(Instant, u64)> = None;
+                while !stop.load(Ordering::Relaxed) {
+                    let now = Instant::now();
+                    let counters = match read_proc_counters() {
+                        Ok(counters) => counters,
+                        Err(err) => {
+                            warn!("Stopped sampling the resource usage: {}", err);
+                            break;
+                        }
+                    };
+                    if let Some((previous_time, previous_ticks)) = previous {
+                        let elapsed = (now - previous_time).as_secs_f64();
+                        if elapsed <= 0.0 {
+                            async_std::task::sleep(interval).await;
+                            continue;
+                        }
+                        let cpu_secs = (counters.cpu_ticks - previous_ticks) as f64 / USER_HZ;
+                        samples.push(ResourceSample {
+                            time: now.saturating_duration_since(start).as_millis(),
+                            cpu_percent: cpu_secs / elapsed * 100.0,
+                            rss: counters.rss,
+                            vms: counters.vms,
+                            thread_num: counters.thread_num,
+                            voluntary_ctxt_switches: counters.voluntary_ctxt_switches,
+                            nonvoluntary_ctxt_switches: counters.nonvoluntary_ctxt_switches,
+                        });
+                    }
+                    previous = Some((now, counters.cpu_ticks));
+                    async_std::task::sleep(interval).await;
+                }
+                samples
+            }
+        });
+        ResourceSampler { stop, handle }
+    }
+
+    /// Stops sampling and returns the samples.
+    pub async fn stop(self) -> Vec<ResourceSample> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.await
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    common::*,
+    histogram::LatencyHistogram,
+    qos::ClassStats,
+    resources::ResourceSample,
+    router::SessionMode,
+    stats::{LatencyStats, PubSubStats},
+    throughput::ThroughputStats,
+    zenoh_config::SessionConfigRecord,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct PubTimeStatus {
+    pub start_pub_worker: u128,
+    pub session_start: Option<u128>,
+    pub pub_sub_worker_start: Option<u128>,
+    pub before_sending: u128,
+    pub start_sending: u128,
+    pub after_sending: u128,
+    /// The number of messages the publisher sent.
+    pub sent_msg_num: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct SubTimeStatus {
+    /// The wall-clock time (seconds since the UNIX epoch) when the process started its round.
+    /// Only recorded by the multi-process workers.
+    pub process_start_sec: Option<i64>,
+    /// The milliseconds part of the wall-clock time when the process started its round.
+    pub process_start_millis: Option<i16>,
+    pub start_sub_worker: u128,
+    pub session_start: Option<u128>,
+    pub pub_sub_worker_start: Option<u128>,
+    pub after_subscribing: u128,
+    pub start_receiving: u128,
+    pub after_receiving: u128,
+}
+
+/// The parameters of the experiment a peer took part in, recorded by the multi-process workers.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ShortConfig {
+    pub peer_id: usize,
+    pub total_put_number: usize,
+    pub num_msgs_per_peer: usize,
+    pub payload_size: usize,
+    pub round_timeout: u64,
+    pub init_time: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeerResult {
+    pub short_config: Option<ShortConfig>,
+    pub peer_id: usize,
+    /// The ratio of unique messages received, duplicates are not counted.
+    pub receive_rate: f64,
+    pub recvd_msg_num: usize,
+    pub expected_msg_num: usize,
+    pub lost_msg_num: usize,
+    pub duplicated_msg_num: usize,
+    pub out_of_order_msg_num: usize,
+    pub latency: Option<LatencyStats>,
+    /// The histogram the latency statistics are computed from. Unit: nanoseconds (ns).
+    pub latency_histogram: LatencyHistogram,
+    pub throughput: Option<ThroughputStats>,
+    /// The number of queries or pings not fully answered before the reply timeout.
+    pub timed_out_request_num: Option<usize>,
+    /// The CPU and memory usage of the process over the round, if sampled by a multi-process
+    /// worker.
+    #[serde(default)]
+    pub resource_usage: Option<Vec<ResourceSample>>,
+    /// The effective zenoh config of every kind of session of a multi-process worker.
+    #[serde(default)]
+    pub session_configs: Vec<SessionConfigRecord>,
+}
+
+/// The result of a whole experiment. `C` is the CLI of the binary running the experiment.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestResult<C> {
+    pub config: C,
+    /// The mode the sessions of the workers were opened in.
+    #[serde(default = "default_session_mode")]
+    pub session_mode: SessionMode,
+    /// The number of in-process routers the sessions went through.
+    #[serde(default)]
+    pub router_num: usize,
+    /// The effective zenoh config of every kind of session of the experiment.
+    #[serde(default)]
+    pub session_configs: Vec<SessionConfigRecord>,
+    pub total_sub_returned: usize,
+    pub total_receive_rate: f64,
+    pub latency: Option<LatencyStats>,
+    /// The merged latency histograms of all the subscribers, so that the percentiles of `latency`
+    /// are the ones of all the messages rather than averages of the subscribers' percentiles.
+    pub latency_histogram: LatencyHistogram,
+    /// The traffic received by all the subscribers together.
+    pub throughput: Option<ThroughputStats>,
+    /// The number of queries or pings not fully answered before the reply timeout.
+    pub timed_out_request_num: Option<usize>,
+    pub per_peer_result: Vec<PeerResult>,
+    /// The delivery statistics of every (publisher, subscriber) pair.
+    pub pub_sub_matrix: Vec<PubSubStats>,
+    /// The delivery and latency statistics of every class of publishers, with publisher groups.
+    #[serde(default)]
+    pub class_stats: Vec<ClassStats>,
+    /// The CPU and memory usage of the process over the round, if sampled.
+    pub resource_usage: Option<Vec<ResourceSample>>,
+}
+
+/// The mode of the results written before the session mode was recorded.
+fn default_session_mode() -> SessionMode {
+    SessionMode::Peer
+}
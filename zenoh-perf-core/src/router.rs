@@ -0,0 +1,68 @@
+use crate::{
+    common::*,
+    config::SessionRole,
+    transport::free_tcp_ports,
+    zenoh_config::{add_connect, add_listen, ZenohConfigs},
+};
+
+/// The mode the sessions of the workers are opened in.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionMode {
+    Peer,
+    Client,
+}
+
+impl SessionMode {
+    pub fn whatami(&self) -> WhatAmI {
+        match self {
+            SessionMode::Peer => WhatAmI::Peer,
+            SessionMode::Client => WhatAmI::Client,
+        }
+    }
+}
+
+/// Routers running in the process, each listening on its own loopback port, router `i`
+/// connecting to router `i - 1` so that the messages between the first and the last router go
+/// through all of them.
+pub struct RouterChain {
+    sessions: Vec<Session>,
+    /// The endpoint of every router, in the order of the chain.
+    pub endpoints: Vec<String>,
+}
+
+impl RouterChain {
+    /// Starts `router_num` routers, with the router config files of `zenoh_configs`.
+    pub async fn start(router_num: usize, zenoh_configs: &ZenohConfigs) -> Result<Self> {
+        let endpoints = free_tcp_ports(router_num)?
+            .iter()
+            .map(|port| format!("tcp/127.0.0.1:{}", port))
+            .collect::<Vec<_>>();
+        let mut sessions = vec![];
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            let role = SessionRole::Router(index);
+            let mut config = zenoh_configs.config(role);
+            config.set_mode(Some(WhatAmI::Router)).unwrap();
+            add_listen(&mut config, [endpoint.parse().unwrap()]);
+            if index > 0 {
+                add_connect(&mut config, [endpoints[index - 1].parse().unwrap()]);
+            }
+            // Routers discovering each other would short-cut the chain.
+            config
+                .insert_json5("scouting/multicast/enabled", "false")
+                .unwrap();
+            zenoh_configs.record(role, &config);
+            sessions.push(zenoh::open(config).await.unwrap());
+        }
+        Ok(RouterChain {
+            sessions,
+            endpoints,
+        })
+    }
+
+    pub async fn close(self) {
+        for session in self.sessions {
+            session.close().await.unwrap();
+        }
+    }
+}
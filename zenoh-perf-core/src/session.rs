@@ -0,0 +1,130 @@
+use crate::{
+    common::*,
+    config::{SessionRole, WorkerConfig},
+    resources::{ResourceSample, ResourceSampler},
+    zenoh_config::add_connect,
+};
+use std::io::Write;
+
+/// The peers seen by a session over time, written by `session_info_worker`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionTimeStatus {
+    pub session_start: Option<u128>,
+    pub pub_sub_worker_start: Option<u128>,
+    pub list_start_timestamp: Vec<u128>,
+    pub list_sess_start_timestamp: Vec<u128>,
+    pub list_timestamp_peer_num: Vec<usize>,
+    pub list_timestamp_res: Vec<Vec<String>>,
+    pub session_id: Option<String>,
+    /// The CPU and memory usage of the process over the round, if sampled.
+    #[serde(default)]
+    pub resource_usage: Option<Vec<ResourceSample>>,
+}
+
+/// The config of a peer session connecting to `locators`.
+pub fn session_config(locators: &[Locator]) -> Config {
+    let mut config = config::default();
+    let endpoints = locators
+        .iter()
+        .map(|locator| EndPoint::from(locator.clone()))
+        .collect::<Vec<_>>();
+    let connect_config = ConnectConfig { endpoints };
+    config.set_connect(connect_config).unwrap();
+    config
+}
+
+/// Opens a peer session connecting to `locators`.
+pub async fn open_session(locators: &[Locator]) -> Session {
+    zenoh::open(session_config(locators)).await.unwrap()
+}
+
+/// Opens a session and polls its info every 100 ms until the round times out, recording how many
+/// peers it is connected to.
+pub async fn session_info_worker(
+    peer_id: usize,
+    config: WorkerConfig,
+    scout_delay: Option<u64>,
+) -> Result<()> {
+    let start = config.start;
+    let timeout = config.timeout();
+    let pub_sub_worker_start = Instant::now();
+    let resource_sampler = config
+        .resource_interval
+        .map(|interval| ResourceSampler::spawn(start, Duration::from_millis(interval)));
+    let role = SessionRole::PubSub(peer_id);
+    let mut zenoh_config = config.zenoh_configs.config(role);
+
+    let endpoints = config
+        .locators
+        .iter()
+        .map(|locator| EndPoint::from(locator.clone()));
+    add_connect(&mut zenoh_config, endpoints);
+
+    if scout_delay.is_some() {
+        zenoh_config.scouting.set_delay(scout_delay).unwrap();
+    }
+    config.zenoh_configs.record(role, &zenoh_config);
+    let zenoh = Arc::new(zenoh::open(zenoh_config).await.unwrap());
+    let session_start_time = Instant::now();
+    let mut list_start_timestamp: Vec<u128> = vec![];
+    let mut list_sess_start_timestamp: Vec<u128> = vec![];
+    let mut list_timestamp_peer_num: Vec<usize> = vec![];
+    let mut list_timestamp_res: Vec<Vec<String>> = vec![];
+    let mut session_id: Option<String> = None;
+
+    while Instant::now() < timeout || session_id.is_none() {
+        let session_info = zenoh.info().await;
+        let after_session_info = Instant::now();
+        if session_id.is_none() {
+            session_id = Some(session_info.get(&0).unwrap().clone());
+        }
+        list_start_timestamp.push((after_session_info - start).as_millis());
+        list_sess_start_timestamp.push((after_session_info - session_start_time).as_millis());
+        let curr_peer_num = session_info
+            .get(&1)
+            .unwrap()
+            .split(',')
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<String>>();
+        list_timestamp_peer_num.push(curr_peer_num.len());
+        list_timestamp_res.push(curr_peer_num);
+
+        async_std::task::sleep(Duration::from_millis(100)).await;
+    }
+
+    let zenoh = Arc::try_unwrap(zenoh).map_err(|_| ()).unwrap();
+    zenoh.close().await.unwrap();
+    let resource_usage = match resource_sampler {
+        Some(resource_sampler) => Some(resource_sampler.stop().await),
+        None => None,
+    };
+
+    let file_path = config.output_dir.join(format!(
+        "Session_{}_info_{}.json",
+        peer_id,
+        config.exp_name()
+    ));
+    let session_start = Some((session_start_time - start).as_millis());
+    let pus_sub_work_start_dur = Some((pub_sub_worker_start - start).as_millis());
+    let test_result = SessionTimeStatus {
+        session_start,
+        pub_sub_worker_start: pus_sub_work_start_dur,
+        list_start_timestamp,
+        list_sess_start_timestamp,
+        list_timestamp_peer_num,
+        list_timestamp_res,
+        session_id,
+        resource_usage,
+    };
+
+    let mut file = std::fs::File::create(file_path).unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&test_result).unwrap()
+    )
+    .unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use crate::{common::*, histogram::LatencyHistogram};
+use std::collections::VecDeque;
+
+/// One-way latency statistics of the received messages. Unit: microseconds (us).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct LatencyStats {
+    pub sample_num: usize,
+    pub min: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+impl LatencyStats {
+    /// Computes the statistics from a histogram of latencies in nanoseconds.
+    /// Returns `None` if the histogram is empty.
+    pub fn from_histogram(histogram: &LatencyHistogram) -> Option<Self> {
+        let percentile = |p: f64| histogram.value_at_percentile(p).map(|value| value / 1000);
+        Some(LatencyStats {
+            sample_num: histogram.count as usize,
+            min: histogram.min? / 1000,
+            mean: histogram.mean()? / 1000.0,
+            p50: percentile(50.0)?,
+            p90: percentile(90.0)?,
+            p99: percentile(99.0)?,
+            p999: percentile(99.9)?,
+            max: histogram.max? / 1000,
+        })
+    }
+}
+
+/// The ratio of the `expected` messages that were `received`, 0 if none was expected (e.g. a
+/// subscriber matching no publisher), rather than NaN.
+pub fn receive_rate(received: usize, expected: usize) -> f64 {
+    if expected == 0 {
+        0.0
+    } else {
+        received as f64 / expected as f64
+    }
+}
+
+/// The delivery statistics of the messages sent by one publisher and received by one subscriber.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct PubSubStats {
+    pub pub_peer_id: usize,
+    pub sub_peer_id: usize,
+    pub recvd_msg_num: usize,
+    pub expected_msg_num: usize,
+    pub lost_msg_num: usize,
+    pub duplicated_msg_num: usize,
+    pub out_of_order_msg_num: usize,
+    /// The messages whose sequence number is out of range.
+    #[serde(default)]
+    pub rejected_msg_num: usize,
+}
+
+/// The number of sequence numbers a `SeqTracker` remembers below the highest one received.
+const SEQ_WINDOW_WORDS: usize = 1024;
+
+/// Tracks the sequence numbers received by a subscriber from a single publisher.
+///
+/// Only the last `64 * SEQ_WINDOW_WORDS` sequence numbers are remembered, so that the memory does
+/// not grow with the number of messages: a message older than that is counted as a late arrival,
+/// not checked for duplicates.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SeqTracker {
+    /// The bits of the sequence numbers from `window_start` on.
+    bitmap: VecDeque<u64>,
+    /// The first sequence number of the window, a multiple of 64.
+    window_start: u64,
+    max_seq: Option<u64>,
+    unique: usize,
+    duplicated: usize,
+    out_of_order: usize,
+    /// The sequence numbers from this one on are out of range, when the number of messages the
+    /// publisher sends is known.
+    seq_limit: Option<u64>,
+    rejected: usize,
+}
+
+impl SeqTracker {
+    /// A tracker rejecting the sequence numbers from `seq_limit` on, e.g. corrupted ones.
+    pub fn with_limit(seq_limit: Option<usize>) -> Self {
+        SeqTracker {
+            seq_limit: seq_limit.map(|limit| limit as u64),
+            ..SeqTracker::default()
+        }
+    }
+
+    /// Records the arrival of the message with sequence number `seq`.
+    /// Returns `false` if the message was already received or is out of range.
+    pub fn record(&mut self, seq: u64) -> bool {
+        if matches!(self.seq_limit, Some(limit) if seq >= limit) {
+            self.rejected += 1;
+            return false;
+        }
+        if seq < self.window_start {
+            self.unique += 1;
+            self.out_of_order += 1;
+            return true;
+        }
+        let mut word = ((seq - self.window_start) / 64) as usize;
+        if word >= SEQ_WINDOW_WORDS {
+            // Slide the window so that `seq` falls in its last word.
+            let shift = word + 1 - SEQ_WINDOW_WORDS;
+            self.bitmap.drain(..shift.min(self.bitmap.len()));
+            self.window_start += shift as u64 * 64;
+            word = SEQ_WINDOW_WORDS - 1;
+        }
+        if word >= self.bitmap.len() {
+            self.bitmap.resize(word + 1, 0);
+        }
+        let bit = 1u64 << (seq % 64);
+        if self.bitmap[word] & bit != 0 {
+            self.duplicated += 1;
+            return false;
+        }
+        self.bitmap[word] |= bit;
+        self.unique += 1;
+        match self.max_seq {
+            Some(max_seq) if seq < max_seq => self.out_of_order += 1,
+            _ => self.max_seq = Some(seq),
+        }
+        true
+    }
+
+    /// The number of distinct messages received, duplicates are not counted.
+    pub fn unique_msg_num(&self) -> usize {
+        self.unique
+    }
+
+    /// The number of messages the publisher sent up to the highest sequence number received.
+    /// Used as the expected number when the subscriber does not know how many were sent.
+    pub fn seq_span(&self) -> usize {
+        // A corrupted header may carry `u64::MAX`.
+        self.max_seq
+            .map_or(0, |max_seq| (max_seq as usize).saturating_add(1))
+    }
+
+    pub fn stats(&self, pub_peer_id: usize, sub_peer_id: usize, expected: usize) -> PubSubStats {
+        PubSubStats {
+            pub_peer_id,
+            sub_peer_id,
+            recvd_msg_num: self.unique,
+            expected_msg_num: expected,
+            lost_msg_num: expected.saturating_sub(self.unique),
+            duplicated_msg_num: self.duplicated,
+            out_of_order_msg_num: self.out_of_order,
+            rejected_msg_num: self.rejected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seq_tracker_counts_in_order_messages() {
+        let mut tracker = SeqTracker::default();
+        for seq in 0..100 {
+            assert!(tracker.record(seq));
+        }
+        let stats = tracker.stats(1, 2, 100);
+        assert_eq!((stats.pub_peer_id, stats.sub_peer_id), (1, 2));
+        assert_eq!(stats.recvd_msg_num, 100);
+        assert_eq!(stats.lost_msg_num, 0);
+        assert_eq!(stats.duplicated_msg_num, 0);
+        assert_eq!(stats.out_of_order_msg_num, 0);
+        assert_eq!(tracker.seq_span(), 100);
+    }
+
+    #[test]
+    fn seq_tracker_counts_duplicates_once() {
+        let mut tracker = SeqTracker::default();
+        for seq in [0, 1, 1, 2, 1, 2] {
+            tracker.record(seq);
+        }
+        let stats = tracker.stats(0, 0, 3);
+        assert_eq!(tracker.unique_msg_num(), 3);
+        assert_eq!(stats.duplicated_msg_num, 3);
+        assert_eq!(stats.lost_msg_num, 0);
+        assert_eq!(stats.out_of_order_msg_num, 0);
+    }
+
+    #[test]
+    fn seq_tracker_counts_gaps_as_lost() {
+        let mut tracker = SeqTracker::default();
+        for seq in [0, 1, 5, 6, 63, 64, 200] {
+            assert!(tracker.record(seq));
+        }
+        assert_eq!(tracker.seq_span(), 201);
+        let stats = tracker.stats(0, 0, 250);
+        assert_eq!(stats.recvd_msg_num, 7);
+        assert_eq!(stats.lost_msg_num, 243);
+        assert_eq!(stats.out_of_order_msg_num, 0);
+        // More received than expected is not a negative loss.
+        assert_eq!(tracker.stats(0, 0, 3).lost_msg_num, 0);
+    }
+
+    #[test]
+    fn seq_tracker_counts_reordered_messages() {
+        let mut tracker = SeqTracker::default();
+        for seq in [0, 2, 1, 3, 70, 65, 64, 71] {
+            assert!(tracker.record(seq));
+        }
+        let stats = tracker.stats(0, 0, 72);
+        assert_eq!(stats.recvd_msg_num, 8);
+        assert_eq!(stats.out_of_order_msg_num, 3);
+        assert_eq!(stats.duplicated_msg_num, 0);
+        assert_eq!(tracker.seq_span(), 72);
+        // A reordered duplicate is a duplicate only.
+        assert!(!tracker.record(65));
+        assert_eq!(tracker.stats(0, 0, 72).out_of_order_msg_num, 3);
+    }
+
+    #[test]
+    fn seq_tracker_rejects_the_seqs_out_of_range() {
+        let mut tracker = SeqTracker::with_limit(Some(10));
+        assert!(tracker.record(9));
+        assert!(!tracker.record(10));
+        assert!(!tracker.record(u64::MAX));
+        let stats = tracker.stats(0, 0, 10);
+        assert_eq!(stats.recvd_msg_num, 1);
+        assert_eq!(stats.rejected_msg_num, 2);
+        assert_eq!(tracker.seq_span(), 10);
+    }
+
+    #[test]
+    fn seq_tracker_memory_is_bounded() {
+        let mut tracker = SeqTracker::default();
+        assert!(tracker.record(u64::MAX - 1));
+        assert!(tracker.bitmap.len() <= SEQ_WINDOW_WORDS);
+        for seq in 0..1_000_000 {
+            tracker.record(seq);
+        }
+        assert!(tracker.bitmap.len() <= SEQ_WINDOW_WORDS);
+    }
+
+    #[test]
+    fn seq_tracker_span_of_the_highest_seq() {
+        let mut tracker = SeqTracker::default();
+        assert_eq!(tracker.seq_span(), 0);
+        assert!(tracker.record(u64::MAX));
+        assert_eq!(tracker.seq_span(), usize::MAX);
+        assert!(tracker.record(3));
+        assert_eq!(tracker.seq_span(), usize::MAX);
+        assert!(tracker.bitmap.len() <= SEQ_WINDOW_WORDS);
+        assert_eq!(tracker.stats(0, 0, tracker.seq_span()).recvd_msg_num, 2);
+    }
+
+    #[test]
+    fn seq_tracker_detects_duplicates_in_the_window_only() {
+        let mut tracker = SeqTracker::default();
+        let window = 64 * SEQ_WINDOW_WORDS as u64;
+        assert!(tracker.record(0));
+        assert!(!tracker.record(0));
+        assert!(tracker.record(2 * window));
+        // Too old to be checked: a late arrival.
+        assert!(tracker.record(0));
+        assert!(tracker.record(2 * window - 1));
+        assert!(!tracker.record(2 * window - 1));
+        let stats = tracker.stats(0, 0, 0);
+        assert_eq!(stats.duplicated_msg_num, 2);
+        assert_eq!(stats.out_of_order_msg_num, 2);
+    }
+
+    #[test]
+    fn receive_rate_without_expected_messages_is_zero() {
+        assert_eq!(receive_rate(0, 0), 0.0);
+        assert_eq!(receive_rate(3, 4), 0.75);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::common::*;
+
+/// The length of the windows the received traffic is counted in. Unit: milliseconds (ms).
+pub const THROUGHPUT_WINDOW_MS: u64 = 1000;
+
+/// The traffic received during one window.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct ThroughputWindow {
+    /// The start of the window, relative to the instant publishers start sending. Unit: ms.
+    pub window_start: u64,
+    pub msgs_per_sec: f64,
+    pub bytes_per_sec: f64,
+}
+
+/// The throughput time series of one or several subscribers, with the aggregate goodput.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThroughputStats {
+    pub window_ms: u64,
+    pub series: Vec<ThroughputWindow>,
+    pub recvd_msg_num: u64,
+    pub recvd_bytes: u64,
+    /// The time between the first and the last received message. Unit: ms.
+    pub active_duration: f64,
+    /// The distinct messages received per second of `active_duration`, duplicates are not counted.
+    pub goodput_msgs_per_sec: f64,
+    /// The payload bytes of the distinct messages received per second of `active_duration`.
+    pub goodput_bytes_per_sec: f64,
+}
+
+/// Counts the messages and bytes received in consecutive windows of `THROUGHPUT_WINDOW_MS`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThroughputMeter {
+    /// The time (ns since the UNIX epoch) the first window starts at.
+    origin_nanos: u64,
+    /// The (messages, bytes) received in each window.
+    windows: Vec<(u64, u64)>,
+    first_recv_nanos: Option<u64>,
+    last_recv_nanos: Option<u64>,
+    unique_msgs: u64,
+    unique_bytes: u64,
+}
+
+impl ThroughputMeter {
+    pub fn new(origin_nanos: u64) -> Self {
+        ThroughputMeter {
+            origin_nanos,
+            windows: Vec::new(),
+            first_recv_nanos: None,
+            last_recv_nanos: None,
+            unique_msgs: 0,
+            unique_bytes: 0,
+        }
+    }
+
+    /// Moves the origin back to `origin_nanos`, shifting the windows by the number of whole
+    /// windows in between.
+    fn rebase(&mut self, origin_nanos: u64) {
+        let window_nanos = THROUGHPUT_WINDOW_MS * 1_000_000;
+        let shift =
+            (self.origin_nanos.saturating_sub(origin_nanos) + window_nanos / 2) / window_nanos;
+        self.windows.splice(0..0, vec![(0, 0); shift as usize]);
+        self.origin_nanos = self.origin_nanos.min(origin_nanos);
+    }
+
+    /// Records a message of `bytes` bytes received at `recv_nanos`. `unique` tells whether it is
+    /// the first copy of the message, only those count towards the goodput.
+    pub fn record(&mut self, recv_nanos: u64, bytes: usize, unique: bool) {
+        let window_nanos = THROUGHPUT_WINDOW_MS * 1_000_000;
+        let window = (recv_nanos.saturating_sub(self.origin_nanos) / window_nanos) as usize;
+        if window >= self.windows.len() {
+            self.windows.resize(window + 1, (0, 0));
+        }
+        self.windows[window].0 += 1;
+        self.windows[window].1 += bytes as u64;
+        self.first_recv_nanos = Some(
+            self.first_recv_nanos
+                .map_or(recv_nanos, |t| t.min(recv_nanos)),
+        );
+        self.last_recv_nanos = Some(
+            self.last_recv_nanos
+                .map_or(recv_nanos, |t| t.max(recv_nanos)),
+        );
+        if unique {
+            self.unique_msgs += 1;
+            self.unique_bytes += bytes as u64;
+        }
+    }
+
+    /// Adds the traffic counted by `other`. The windows of both meters are re-based onto the
+    /// earlier of the two origins first, rounded to whole windows.
+    pub fn merge(&mut self, other: &ThroughputMeter) {
+        let mut other = other.clone();
+        self.rebase(other.origin_nanos);
+        other.rebase(self.origin_nanos);
+        if other.windows.len() > self.windows.len() {
+            self.windows.resize(other.windows.len(), (0, 0));
+        }
+        for (window, (msgs, bytes)) in self.windows.iter_mut().zip(other.windows.iter()) {
+            window.0 += msgs;
+            window.1 += bytes;
+        }
+        self.first_recv_nanos = match (self.first_recv_nanos, other.first_recv_nanos) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.last_recv_nanos = match (self.last_recv_nanos, other.last_recv_nanos) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        self.unique_msgs += other.unique_msgs;
+        self.unique_bytes += other.unique_bytes;
+    }
+
+    /// Returns `None` if nothing was received.
+    pub fn stats(&self) -> Option<ThroughputStats> {
+        let first_recv_nanos = self.first_recv_nanos?;
+        let last_recv_nanos = self.last_recv_nanos?;
+        let window_secs = THROUGHPUT_WINDOW_MS as f64 / 1000.0;
+        let series = self
+            .windows
+            .iter()
+            .enumerate()
+            .map(|(idx, (msgs, bytes))| ThroughputWindow {
+                window_start: idx as u64 * THROUGHPUT_WINDOW_MS,
+                msgs_per_sec: *msgs as f64 / window_secs,
+                bytes_per_sec: *bytes as f64 / window_secs,
+            })
+            .collect::<Vec<_>>();
+        let active_secs = (last_recv_nanos - first_recv_nanos) as f64 / 1e9;
+        let per_sec = |count: u64| {
+            if active_secs > 0.0 {
+                count as f64 / active_secs
+            } else {
+                0.0
+            }
+        };
+        Some(ThroughputStats {
+            window_ms: THROUGHPUT_WINDOW_MS,
+            series,
+            recvd_msg_num: self.windows.iter().map(|(msgs, _)| msgs).sum(),
+            recvd_bytes: self.windows.iter().map(|(_, bytes)| bytes).sum(),
+            active_duration: active_secs * 1000.0,
+            goodput_msgs_per_sec: per_sec(self.unique_msgs),
+            goodput_bytes_per_sec: per_sec(self.unique_bytes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    fn series(meter: &ThroughputMeter) -> Vec<f64> {
+        meter
+            .stats()
+            .unwrap()
+            .series
+            .iter()
+            .map(|window| window.msgs_per_sec)
+            .collect()
+    }
+
+    #[test]
+    fn records_into_windows_from_the_origin() {
+        let mut meter = ThroughputMeter::new(10 * SEC);
+        meter.record(10 * SEC, 100, true);
+        meter.record(12 * SEC + 1, 100, true);
+        meter.record(12 * SEC + 2, 100, false);
+        let stats = meter.stats().unwrap();
+        assert_eq!(series(&meter), vec![1.0, 0.0, 2.0]);
+        assert_eq!((stats.recvd_msg_num, stats.recvd_bytes), (3, 300));
+        assert!((stats.goodput_msgs_per_sec - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn merge_rebases_onto_the_earlier_origin() {
+        let mut early = ThroughputMeter::new(10 * SEC);
+        early.record(10 * SEC, 1, true);
+        let mut late = ThroughputMeter::new(12 * SEC);
+        late.record(12 * SEC, 1, true);
+        late.record(13 * SEC, 1, true);
+
+        let mut total = early.clone();
+        total.merge(&late);
+        assert_eq!(series(&total), vec![1.0, 0.0, 1.0, 1.0]);
+        let mut total = late;
+        total.merge(&early);
+        assert_eq!(series(&total), vec![1.0, 0.0, 1.0, 1.0]);
+        assert_eq!(total.stats().unwrap().active_duration, 3000.0);
+    }
+
+    #[test]
+    fn empty_meter_has_no_stats() {
+        let mut meter = ThroughputMeter::new(SEC);
+        assert!(meter.stats().is_none());
+        meter.merge(&ThroughputMeter::new(0));
+        assert!(meter.stats().is_none());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    common::*,
+    transport::free_tcp_ports,
+    zenoh_config::{add_connect, add_listen},
+};
+use std::collections::BTreeSet;
+
+/// The graphs the in-process peers can be linked in.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TopologyKind {
+    /// Every peer connects to peer 0.
+    Star,
+    /// Peer `i` connects to peer `i - 1`.
+    Chain,
+    /// A chain whose last peer also connects to peer 0.
+    Ring,
+    /// Every peer connects to all the others.
+    FullMesh,
+    /// Peer `i` connects to its parent `(i - 1) / degree`.
+    Tree,
+    /// Every peer has `degree` neighbours drawn at random.
+    RandomRegular,
+}
+
+/// How the in-process peers are linked to each other.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Topology {
+    pub kind: TopologyKind,
+    /// The number of children of a node with `Tree`, of neighbours with `RandomRegular`.
+    pub degree: usize,
+    /// The seed of the random graph with `RandomRegular`.
+    pub seed: u64,
+}
+
+/// The endpoint a peer listens on and the endpoints of the peers it connects to.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeerEndpoints {
+    pub listen: String,
+    pub connect: Vec<String>,
+}
+
+impl PeerEndpoints {
+    /// Makes a peer session listen and connect to these endpoints: the multicast scouting is
+    /// disabled, so that the peers do not discover each other outside of the topology.
+    pub fn apply(&self, config: &mut Config) {
+        let endpoint = |locator: &String| locator.parse::<EndPoint>().unwrap();
+        add_listen(config, [endpoint(&self.listen)]);
+        add_connect(config, self.connect.iter().map(endpoint));
+        config
+            .insert_json5("scouting/multicast/enabled", "false")
+            .unwrap();
+    }
+}
+
+impl Topology {
+    /// The links between `peer_num` peers, as (connecting peer, listening peer) pairs.
+    /// Every link appears once, the peer with the higher id connecting to the other one.
+    pub fn links(&self, peer_num: usize) -> Result<Vec<(usize, usize)>> {
+        let links = match self.kind {
+            TopologyKind::Star => (1..peer_num).map(|peer| (peer, 0)).collect(),
+            TopologyKind::Chain => (1..peer_num).map(|peer| (peer, peer - 1)).collect(),
+            TopologyKind::Ring => {
+                let mut links = (1..peer_num)
+                    .map(|peer| (peer, peer - 1))
+                    .collect::<Vec<_>>();
+                // Two peers are already linked both ways by the chain.
+                if peer_num > 2 {
+                    links.push((peer_num - 1, 0));
+                }
+                links
+            }
+            TopologyKind::FullMesh => (0..peer_num)
+                .flat_map(|peer| (0..peer).map(move |other| (peer, other)))
+                .collect(),
+            TopologyKind::Tree => {
+                let degree = self.degree.max(1);
+                (1..peer_num)
+                    .map(|peer| (peer, (peer - 1) / degree))
+                    .collect()
+            }
+            TopologyKind::RandomRegular => random_regular(peer_num, self.degree, self.seed)?,
+        };
+        Ok(links)
+    }
+
+    /// Assigns every peer its own TCP endpoint on the loopback, on a free port, and the endpoints
+    /// of the peers it connects to.
+    pub fn peer_endpoints(&self, peer_num: usize) -> Result<Vec<PeerEndpoints>> {
+        let listen = free_tcp_ports(peer_num)?
+            .iter()
+            .map(|port| format!("tcp/127.0.0.1:{}", port))
+            .collect::<Vec<_>>();
+        let mut endpoints = listen
+            .iter()
+            .map(|listen| PeerEndpoints {
+                listen: listen.clone(),
+                connect: vec![],
+            })
+            .collect::<Vec<_>>();
+        for (peer, other) in self.links(peer_num)? {
+            endpoints[peer].connect.push(listen[other].clone());
+        }
+        Ok(endpoints)
+    }
+}
+
+/// A random `degree`-regular graph over `peer_num` peers.
+///
+/// The graph is drawn with the algorithm of Steger and Wormald: the `degree` slots of every peer
+/// are paired one pair at a time, each pair being drawn at random among the ones that would link
+/// two distinct peers not linked yet, and the draw restarts in the rare case no such pair is
+/// left. A graph of degree above `(peer_num - 1) / 2` is drawn as the complement of a sparser one,
+/// where restarts are rarer.
+fn random_regular(peer_num: usize, degree: usize, seed: u64) -> Result<Vec<(usize, usize)>> {
+    if degree >= peer_num || (peer_num * degree) % 2 == 1 {
+        anyhow::bail!(
+            "no {}-regular graph over {} peers: the degree must be lower than the number of peers, and their product even",
+            degree,
+            peer_num
+        );
+    }
+    if 2 * degree > peer_num - 1 {
+        let complement = random_regular(peer_num, peer_num - 1 - degree, seed)?
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        return Ok((0..peer_num)
+            .flat_map(|peer| (0..peer).map(move |other| (peer, other)))
+            .filter(|link| !complement.contains(link))
+            .collect());
+    }
+    const MAX_ATTEMPTS: usize = 1000;
+    // xorshift64, whose state must not be 0.
+    let mut state = match seed ^ 0x9E37_79B9_7F4A_7C15 {
+        0 => 1,
+        state => state,
+    };
+    let mut next = move |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+    'attempts: for _ in 0..MAX_ATTEMPTS {
+        let mut slots = (0..peer_num)
+            .flat_map(|peer| vec![peer; degree])
+            .collect::<Vec<_>>();
+        let mut links = BTreeSet::new();
+        let mut misses = 0;
+        while !slots.is_empty() {
+            let (i, j) = (next(slots.len()), next(slots.len()));
+            let link = (slots[i].max(slots[j]), slots[i].min(slots[j]));
+            if link.0 == link.1 || links.contains(&link) {
+                misses += 1;
+                // Only look for a suitable pair once random draws keep missing.
+                if misses > slots.len() {
+                    let stuck = slots.iter().enumerate().all(|(i, a)| {
+                        slots[i + 1..]
+                            .iter()
+                            .all(|b| a == b || links.contains(&(*a.max(b), *a.min(b))))
+                    });
+                    if stuck {
+                        continue 'attempts;
+                    }
+                    misses = 0;
+                }
+                continue;
+            }
+            links.insert(link);
+            slots.swap_remove(i.max(j));
+            slots.swap_remove(i.min(j));
+            misses = 0;
+        }
+        return Ok(links.into_iter().collect());
+    }
+    anyhow::bail!(
+        "no {}-regular graph over {} peers found in {} attempts",
+        degree,
+        peer_num,
+        MAX_ATTEMPTS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topology(kind: TopologyKind, degree: usize) -> Topology {
+        Topology {
+            kind,
+            degree,
+            seed: 0,
+        }
+    }
+
+    /// The number of links of every peer.
+    fn degrees(links: &[(usize, usize)], peer_num: usize) -> Vec<usize> {
+        let mut degrees = vec![0; peer_num];
+        for (peer, other) in links {
+            assert!(
+                peer > other && *peer < peer_num,
+                "bad link {:?}",
+                (peer, other)
+            );
+            degrees[*peer] += 1;
+            degrees[*other] += 1;
+        }
+        degrees
+    }
+
+    #[test]
+    fn star_chain_and_tree_are_spanning_trees() {
+        for kind in [TopologyKind::Star, TopologyKind::Chain, TopologyKind::Tree] {
+            for peer_num in [1, 2, 3, 10] {
+                let links = topology(kind, 3).links(peer_num).unwrap();
+                assert_eq!(links.len(), peer_num - 1, "{:?} of {}", kind, peer_num);
+                degrees(&links, peer_num);
+            }
+        }
+        let star = topology(TopologyKind::Star, 0).links(5).unwrap();
+        assert_eq!(degrees(&star, 5), vec![4, 1, 1, 1, 1]);
+        let tree = topology(TopologyKind::Tree, 2).links(7).unwrap();
+        assert_eq!(degrees(&tree, 7), vec![2, 3, 3, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn ring_and_full_mesh() {
+        assert_eq!(topology(TopologyKind::Ring, 0).links(2).unwrap().len(), 1);
+        let ring = topology(TopologyKind::Ring, 0).links(6).unwrap();
+        assert_eq!(degrees(&ring, 6), vec![2; 6]);
+        let mesh = topology(TopologyKind::FullMesh, 0).links(6).unwrap();
+        assert_eq!(mesh.len(), 15);
+        assert_eq!(degrees(&mesh, 6), vec![5; 6]);
+    }
+
+    #[test]
+    fn random_regular_graphs_are_regular() {
+        for (peer_num, degree) in [
+            (4, 3),
+            (10, 3),
+            (10, 7),
+            (16, 8),
+            (20, 12),
+            (50, 7),
+            (64, 20),
+        ] {
+            for seed in 0..5 {
+                let links = random_regular(peer_num, degree, seed).unwrap();
+                assert_eq!(links.len(), peer_num * degree / 2);
+                assert_eq!(links.iter().collect::<BTreeSet<_>>().len(), links.len());
+                assert_eq!(
+                    degrees(&links, peer_num),
+                    vec![degree; peer_num],
+                    "{}-regular over {} peers, seed {}",
+                    degree,
+                    peer_num,
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn random_regular_is_deterministic() {
+        assert_eq!(
+            random_regular(30, 9, 42).unwrap(),
+            random_regular(30, 9, 42).unwrap()
+        );
+        assert_ne!(
+            random_regular(30, 9, 42).unwrap(),
+            random_regular(30, 9, 43).unwrap()
+        );
+    }
+
+    #[test]
+    fn impossible_random_regular_graphs() {
+        assert!(random_regular(5, 3, 0).is_err());
+        assert!(random_regular(4, 4, 0).is_err());
+    }
+}
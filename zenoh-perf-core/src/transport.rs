@@ -0,0 +1,162 @@
+use crate::common::*;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType, IsCa};
+use std::net::{TcpListener, UdpSocket};
+use std::path::{Path, PathBuf};
+
+/// The zenoh transports an experiment can be restricted to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum Transport {
+    Tcp,
+    Udp,
+    UnixsockStream,
+    Tls,
+}
+
+impl Transport {
+    /// The protocol of the transport in the locators.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transport::Tcp => "tcp",
+            Transport::Udp => "udp",
+            Transport::UnixsockStream => "unixsock-stream",
+            Transport::Tls => "tls",
+        }
+    }
+}
+
+/// Allocates `num` distinct free TCP ports on the loopback.
+pub fn free_tcp_ports(num: usize) -> std::io::Result<Vec<u16>> {
+    // The listeners are kept until all the ports are allocated, so that they are distinct.
+    let listeners = (0..num)
+        .map(|_| TcpListener::bind("127.0.0.1:0"))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    listeners
+        .iter()
+        .map(|listener| Ok(listener.local_addr()?.port()))
+        .collect()
+}
+
+/// A self-signed CA and a certificate for `localhost` signed by it, for the TLS transport.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TlsFiles {
+    pub root_ca_certificate: PathBuf,
+    pub server_certificate: PathBuf,
+    pub server_private_key: PathBuf,
+}
+
+impl TlsFiles {
+    /// Generates the certificates and writes them as PEM files into `dir`.
+    pub fn generate(dir: &Path) -> Result<Self> {
+        // The names must differ, or the server certificate would look self-signed.
+        let common_name = |name: &str| {
+            let mut distinguished_name = DistinguishedName::new();
+            distinguished_name.push(DnType::CommonName, name);
+            distinguished_name
+        };
+        let mut ca_params = CertificateParams::new(vec![]);
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.distinguished_name = common_name("zenoh-perf CA");
+        let ca = Certificate::from_params(ca_params)?;
+        let mut server_params = CertificateParams::new(vec!["localhost".to_string()]);
+        server_params.distinguished_name = common_name("localhost");
+        let server = Certificate::from_params(server_params)?;
+
+        let files = TlsFiles {
+            root_ca_certificate: dir.join("ca.pem"),
+            server_certificate: dir.join("server.pem"),
+            server_private_key: dir.join("server_key.pem"),
+        };
+        std::fs::write(&files.root_ca_certificate, ca.serialize_pem()?)?;
+        std::fs::write(
+            &files.server_certificate,
+            server.serialize_pem_with_signer(&ca)?,
+        )?;
+        std::fs::write(
+            &files.server_private_key,
+            server.serialize_private_key_pem(),
+        )?;
+        Ok(files)
+    }
+}
+
+/// How the sessions of an experiment restricted to one transport are set up.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransportSetup {
+    pub transport: Transport,
+    /// The endpoint the listening session listens on, and the other sessions connect to.
+    pub locator: String,
+    /// The certificates of the listening session, and the CA the others trust, with `Tls`.
+    pub tls: Option<TlsFiles>,
+}
+
+impl TransportSetup {
+    /// Allocates a loopback endpoint of `transport`: a free port, or a new socket path for the
+    /// Unix domain sockets. With `Tls`, the certificates are generated into `dir`.
+    pub fn loopback(transport: Transport, dir: &Path) -> Result<Self> {
+        let free_tcp_port = || -> Result<u16> { Ok(free_tcp_ports(1)?[0]) };
+        let (locator, tls) = match transport {
+            Transport::Tcp => (format!("tcp/127.0.0.1:{}", free_tcp_port()?), None),
+            Transport::Udp => {
+                let port = UdpSocket::bind("127.0.0.1:0")?.local_addr()?.port();
+                (format!("udp/127.0.0.1:{}", port), None)
+            }
+            // The socket lives in the temporary directory, as the paths of Unix domain sockets
+            // are limited to about 100 bytes.
+            Transport::UnixsockStream => {
+                let path = std::env::temp_dir().join(format!(
+                    "zenoh-perf-{}-{}.sock",
+                    std::process::id(),
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_nanos()
+                ));
+                (format!("unixsock-stream/{}", path.display()), None)
+            }
+            // The certificate is for `localhost`, not for the IP address.
+            Transport::Tls => (
+                format!("tls/localhost:{}", free_tcp_port()?),
+                Some(TlsFiles::generate(dir)?),
+            ),
+        };
+        Ok(TransportSetup {
+            transport,
+            locator,
+            tls,
+        })
+    }
+
+    pub fn locator(&self) -> Locator {
+        self.locator.parse().unwrap()
+    }
+
+    /// Restricts a session to the transport: the multicast scouting is disabled so that the
+    /// sessions only reach each other through `locator`, and the certificates are set with `Tls`.
+    pub fn apply(&self, config: &mut Config) {
+        config
+            .insert_json5("scouting/multicast/enabled", "false")
+            .unwrap();
+        if let Some(tls) = &self.tls {
+            for (key, path) in [
+                ("root_ca_certificate", &tls.root_ca_certificate),
+                ("server_certificate", &tls.server_certificate),
+                ("server_private_key", &tls.server_private_key),
+            ] {
+                config
+                    .insert_json5(
+                        &format!("transport/link/tls/{}", key),
+                        &serde_json::to_string(path).unwrap(),
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Removes the socket and lock files left by a Unix domain socket listener.
+    pub fn clean_up(&self) {
+        if let Some(path) = self.locator.strip_prefix("unixsock-stream/") {
+            let _ = std::fs::remove_file(path);
+            let _ = std::fs::remove_file(format!("{}.lock", path));
+        }
+    }
+}
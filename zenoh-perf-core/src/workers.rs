@@ -0,0 +1,710 @@
+use crate::{
+    common::*,
+    config::{SessionRole, TestMode, WorkerConfig},
+    histogram::LatencyHistogram,
+    pacing::Pacer,
+    payload::{get_msg_payload, now_nanos, stamp_msg_payload, MsgHeader},
+    ping::{ping_worker, pong_worker},
+    query::{query_worker, queryable_worker},
+    resources::ResourceSampler,
+    result::{PeerResult, PubTimeStatus, SubTimeStatus, TestResult},
+    stats::{receive_rate, LatencyStats, PubSubStats, SeqTracker},
+    throughput::ThroughputMeter,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The numbers of messages the publishers sent, reported to `demonstration_worker` as they are
+/// done. The clones share the same channel.
+#[derive(Debug, Clone)]
+pub struct SentMsgNums {
+    tx: flume::Sender<(usize, usize)>,
+    rx: flume::Receiver<(usize, usize)>,
+}
+
+impl Default for SentMsgNums {
+    fn default() -> Self {
+        let (tx, rx) = flume::unbounded();
+        SentMsgNums { tx, rx }
+    }
+}
+
+impl SentMsgNums {
+    /// Reports that publisher `pub_peer_id` sent `sent_msg_num` messages.
+    pub fn report(&self, pub_peer_id: usize, sent_msg_num: usize) {
+        self.tx.send((pub_peer_id, sent_msg_num)).unwrap();
+    }
+
+    /// Waits until `pub_num` publishers have reported, or until `deadline`, and returns the
+    /// reported numbers by publisher.
+    pub async fn wait(&self, pub_num: usize, deadline: Instant) -> BTreeMap<usize, usize> {
+        let mut sent_msg_nums = BTreeMap::new();
+        while sent_msg_nums.len() < pub_num {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match async_std::future::timeout(remaining, self.rx.recv_async()).await {
+                Ok(Ok((pub_peer_id, sent_msg_num))) => {
+                    sent_msg_nums.insert(pub_peer_id, sent_msg_num);
+                }
+                _ => {
+                    warn!(
+                        "{}/{} publishers reported the number of messages they sent",
+                        sent_msg_nums.len(),
+                        pub_num
+                    );
+                    break;
+                }
+            }
+        }
+        sent_msg_nums
+    }
+}
+
+/// What a subscriber reports to `demonstration_worker` at the end of the round.
+///
+/// The samples are folded into the report as they arrive and dropped right away, so that the
+/// memory used by a subscriber does not grow with the number of messages it receives.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubReport {
+    pub peer_id: usize,
+    /// The number of samples received, duplicates and malformed payloads included.
+    pub recvd_msg_num: usize,
+    /// The total payload size of the received samples. Unit: bytes.
+    pub recvd_bytes: usize,
+    /// The one-way latencies (ns) of the distinct, in-range samples carrying a valid header.
+    pub latency: LatencyHistogram,
+    /// The sequence tracker of every publisher the subscriber heard from.
+    pub seq_trackers: BTreeMap<usize, SeqTracker>,
+    /// The one-way latencies (ns) by class of publishers, only tracked with publisher groups.
+    #[serde(default)]
+    pub class_latency: BTreeMap<String, LatencyHistogram>,
+    /// The messages and bytes received over time.
+    pub throughput: ThroughputMeter,
+    /// The number of queries or pings not fully answered in time, only set in query and ping-pong
+    /// modes.
+    pub timed_out_request_num: Option<usize>,
+}
+
+impl SubReport {
+    pub fn new(peer_id: usize, throughput: ThroughputMeter) -> Self {
+        SubReport {
+            peer_id,
+            recvd_msg_num: 0,
+            recvd_bytes: 0,
+            latency: LatencyHistogram::new(),
+            seq_trackers: BTreeMap::new(),
+            class_latency: BTreeMap::new(),
+            throughput,
+            timed_out_request_num: None,
+        }
+    }
+
+    /// Counts a sample of `bytes` bytes received at `recv_nanos`. `unique` is whether it is the
+    /// first copy of the message, as returned by the sequence tracker.
+    pub fn record_sample(&mut self, recv_nanos: u64, bytes: usize, unique: bool) {
+        self.recvd_msg_num += 1;
+        self.recvd_bytes += bytes;
+        self.throughput.record(recv_nanos, bytes, unique);
+    }
+
+    /// The number of distinct messages received, duplicates are not counted.
+    pub fn unique_msg_num(&self) -> usize {
+        self.seq_trackers
+            .values()
+            .map(|tracker| tracker.unique_msg_num())
+            .sum()
+    }
+
+    /// Summarizes the report into the subscriber result and one row of the (publisher, subscriber)
+    /// matrix, covering the publishers in `pub_peer_ids` the subscriber subscribes to or heard from.
+    pub fn peer_result(
+        &self,
+        pub_peer_ids: &BTreeSet<usize>,
+        config: &WorkerConfig,
+    ) -> (PeerResult, Vec<PubSubStats>) {
+        let unique_msg_num = self.unique_msg_num();
+        let key_space = config.key_space();
+        let peer_matrix = pub_peer_ids
+            .iter()
+            .filter(|pub_id| {
+                key_space.matches(**pub_id, self.peer_id) || self.seq_trackers.contains_key(pub_id)
+            })
+            .map(|pub_id| {
+                let tracker = self.seq_trackers.get(pub_id).cloned().unwrap_or_default();
+                let expected = config.expected_msg_num_from(*pub_id, self.peer_id, &tracker);
+                tracker.stats(*pub_id, self.peer_id, expected)
+            })
+            .collect::<Vec<_>>();
+        let total_msg_num = if config.is_throughput_mode() {
+            peer_matrix.iter().map(|s| s.expected_msg_num).sum()
+        } else {
+            config.expected_msg_num(self.peer_id)
+        };
+        println!(
+            "sub peer {}: total received messages: {}/{} ({} unique)",
+            self.peer_id, self.recvd_msg_num, total_msg_num, unique_msg_num
+        );
+        for stats in peer_matrix.iter() {
+            if stats.recvd_msg_num == 0 {
+                warn!(
+                    "sub peer {}: no message received from pub peer {}",
+                    self.peer_id, stats.pub_peer_id
+                );
+            }
+        }
+        let peer_result = PeerResult {
+            short_config: None,
+            peer_id: self.peer_id,
+            receive_rate: receive_rate(unique_msg_num, total_msg_num),
+            recvd_msg_num: self.recvd_msg_num,
+            expected_msg_num: total_msg_num,
+            lost_msg_num: total_msg_num.saturating_sub(unique_msg_num),
+            duplicated_msg_num: peer_matrix.iter().map(|s| s.duplicated_msg_num).sum(),
+            out_of_order_msg_num: peer_matrix.iter().map(|s| s.out_of_order_msg_num).sum(),
+            latency: LatencyStats::from_histogram(&self.latency),
+            latency_histogram: self.latency.clone(),
+            throughput: self.throughput.stats(),
+            timed_out_request_num: self.timed_out_request_num,
+            resource_usage: None,
+            session_configs: vec![],
+        };
+        (peer_result, peer_matrix)
+    }
+}
+
+/// Collects the reports of all the subscribers, writes the `Exp_*.json` result file and returns its path.
+pub async fn demonstration_worker<C: Serialize>(
+    rx: flume::Receiver<SubReport>,
+    total_sub_number: usize,
+    config: WorkerConfig,
+    args: C,
+) -> PathBuf {
+    let resource_sampler = config
+        .resource_interval
+        .map(|interval| ResourceSampler::spawn(config.start, Duration::from_millis(interval)));
+    let mut vector_data = vec![];
+    while let Ok(data) = rx.recv_async().await {
+        vector_data.push(data);
+    }
+    let resource_usage = match resource_sampler {
+        Some(resource_sampler) => Some(resource_sampler.stop().await),
+        None => None,
+    };
+    println!(
+        "Received data from {}/{} sub peers",
+        vector_data.len(),
+        total_sub_number
+    );
+    vector_data.par_sort_by_key(|report| report.peer_id);
+    // Local publishers are always expected, remote ones are only known once they are heard from.
+    let pub_peer_ids = (0..config.total_put_number)
+        .chain(
+            vector_data
+                .iter()
+                .flat_map(|report| report.seq_trackers.keys().copied()),
+        )
+        .collect::<BTreeSet<_>>();
+
+    let (peer_result, peer_matrix): (Vec<PeerResult>, Vec<_>) = vector_data
+        .par_iter()
+        .map(|report| report.peer_result(&pub_peer_ids, &config))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .unzip();
+    let pub_sub_matrix = peer_matrix.into_iter().flatten().collect::<Vec<_>>();
+    let class_latency = vector_data.iter().fold(
+        BTreeMap::<String, LatencyHistogram>::new(),
+        |mut class_latency, report| {
+            for (class, histogram) in report.class_latency.iter() {
+                class_latency
+                    .entry(class.clone())
+                    .or_default()
+                    .merge(histogram);
+            }
+            class_latency
+        },
+    );
+    // In throughput mode, the subscribers do not know how many messages were sent: the
+    // publishers report it once they are done, which may be after the subscribers with a
+    // blocking congestion control.
+    let sent_msg_nums = if config.is_throughput_mode() && !config.publisher_qos.groups.is_empty() {
+        let grace = Duration::from_millis(config.round_timeout.max(1000));
+        config
+            .sent_msg_nums
+            .wait(config.total_put_number, Instant::now() + grace)
+            .await
+    } else {
+        BTreeMap::new()
+    };
+    let class_stats = config.publisher_qos.class_stats(
+        pub_peer_ids.iter().copied(),
+        &pub_sub_matrix,
+        &class_latency,
+        &sent_msg_nums,
+    );
+    for stats in class_stats.iter() {
+        println!(
+            "class {} ({:?}, {:?}): {}/{} messages received, {} lost, latency (us): p50 = {}, p99 = {}",
+            stats.class,
+            stats.priority,
+            stats.congestion_control,
+            stats.recvd_msg_num,
+            stats.expected_msg_num,
+            stats.lost_msg_num,
+            stats.latency.map_or("-".to_string(), |latency| latency.p50.to_string()),
+            stats.latency.map_or("-".to_string(), |latency| latency.p99.to_string()),
+        );
+    }
+    let total_received_msgs = vector_data
+        .par_iter()
+        .map(|report| report.unique_msg_num())
+        .sum::<usize>();
+    let latency_histogram =
+        vector_data
+            .iter()
+            .fold(LatencyHistogram::new(), |mut histogram, report| {
+                histogram.merge(&report.latency);
+                histogram
+            });
+    let total_latency = LatencyStats::from_histogram(&latency_histogram);
+    if let Some(latency) = &total_latency {
+        println!(
+            "latency (us): min = {}, mean = {:.1}, p50 = {}, p99 = {}, max = {}",
+            latency.min, latency.mean, latency.p50, latency.p99, latency.max
+        );
+    }
+    let total_expected_msgs = peer_result
+        .iter()
+        .map(|result| result.expected_msg_num)
+        .sum::<usize>();
+    let total_receive_rate = receive_rate(total_received_msgs, total_expected_msgs);
+    let total_throughput = vector_data
+        .iter()
+        .map(|report| report.throughput.clone())
+        .reduce(|mut total, throughput| {
+            total.merge(&throughput);
+            total
+        })
+        .and_then(|total| total.stats());
+    if let Some(throughput) = &total_throughput {
+        println!(
+            "goodput: {:.1} msgs/s, {:.1} bytes/s",
+            throughput.goodput_msgs_per_sec, throughput.goodput_bytes_per_sec
+        );
+    }
+    let file_path = config.output_dir.join(format!(
+        "Exp_{}-{}-{}-{}-{}-{}.json",
+        config.total_put_number,
+        total_sub_number,
+        config.num_msgs_per_peer,
+        config.payload_size,
+        config.round_timeout,
+        config.init_time
+    ));
+    let test_result = TestResult {
+        config: args,
+        session_mode: config.session_mode,
+        router_num: config.router_endpoints.len(),
+        session_configs: config.zenoh_configs.records(),
+        total_sub_returned: vector_data.len(),
+        total_receive_rate,
+        latency: total_latency,
+        latency_histogram,
+        throughput: total_throughput,
+        timed_out_request_num: vector_data
+            .iter()
+            .filter_map(|report| report.timed_out_request_num)
+            .reduce(|a, b| a + b),
+        per_peer_result: peer_result,
+        pub_sub_matrix,
+        class_stats,
+        resource_usage,
+    };
+
+    let mut file = std::fs::File::create(&file_path).unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&test_result).unwrap()
+    )
+    .unwrap();
+    file_path
+}
+
+/// Publishes the messages of publisher `peer_id`, or answers queries (pings) in query (ping-pong)
+/// mode.
+pub async fn publish_worker(
+    zenoh: Arc<Session>,
+    peer_id: usize,
+    config: WorkerConfig,
+    session_start_time: Option<Instant>,
+    pub_sub_worker_start: Option<Instant>,
+) -> Result<()> {
+    match config.test_mode {
+        TestMode::PubSub => {}
+        TestMode::Query => {
+            return queryable_worker(
+                zenoh,
+                peer_id,
+                config,
+                session_start_time,
+                pub_sub_worker_start,
+            )
+            .await
+        }
+        TestMode::PingPong => {
+            return pong_worker(
+                zenoh,
+                peer_id,
+                config,
+                session_start_time,
+                pub_sub_worker_start,
+            )
+            .await
+        }
+    }
+    let start = config.start;
+    let start_until = config.start_until();
+    let timeout = config.timeout();
+    let start_worker = Instant::now() - start;
+    let msg_payload = get_msg_payload(config.payload_size, peer_id, config.payload_filler);
+    let key_expr = config.key_space().pub_key(peer_id);
+    let mut timeout_flag = false;
+    let mut session_start = session_start_time;
+    let zenoh_new = if config.multipeer_mode {
+        let zenoh_new = config
+            .open_worker_session(SessionRole::Publisher(peer_id))
+            .await;
+        session_start = Some(Instant::now());
+        Some(zenoh_new)
+    } else {
+        None
+    };
+    let session = zenoh_new.as_ref().unwrap_or(&*zenoh);
+
+    let curr_time = Instant::now();
+    let before_sending = curr_time - start;
+    if start_until > curr_time {
+        async_std::task::sleep(start_until - curr_time).await;
+    }
+    let start_sending = Instant::now() - start;
+    info!("start sending messages");
+    let mut pacer = Pacer::new(&config, peer_id)?;
+    let congestion_control = config.publisher_qos.congestion_control(peer_id);
+    let priority = config.publisher_qos.priority(peer_id);
+    let sent_msg_num = if config.is_throughput_mode() {
+        throughput_publish(
+            session,
+            &key_expr,
+            &msg_payload,
+            peer_id,
+            &config,
+            &mut pacer,
+        )
+        .await
+    } else {
+        let mut sent_msg_num = 0;
+        for msg_id in 0..config.num_msgs_per_peer {
+            pacer.wait().await;
+            let mut payload = msg_payload.clone();
+            stamp_msg_payload(&mut payload, msg_id);
+            session
+                .put(&key_expr, payload)
+                .congestion_control(congestion_control)
+                .priority(priority)
+                .await
+                .unwrap();
+            sent_msg_num += 1;
+            if timeout <= Instant::now() {
+                timeout_flag = true;
+                warn!("publish worker sent message after timeout! Please reduce # of publishers or increase timeout.");
+                break;
+            }
+        }
+        sent_msg_num
+    };
+    config.sent_msg_nums.report(peer_id, sent_msg_num);
+    let after_sending = Instant::now() - start;
+    if let Some(zenoh_new) = zenoh_new {
+        zenoh_new.close().await.unwrap();
+    }
+
+    if timeout_flag {
+        let file_path = config.output_dir.join(format!("info-{}.txt", peer_id));
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(file_path)
+            .unwrap();
+        writeln!(
+            &mut file,
+            "Peer-{} publisher timeout. Exp: {}",
+            peer_id,
+            config.exp_name()
+        )
+        .unwrap();
+    }
+
+    let file_path =
+        config
+            .output_dir
+            .join(format!("put_{}_info_{}.json", peer_id, config.exp_name()));
+    let pub_time_status = PubTimeStatus {
+        start_pub_worker: start_worker.as_millis(),
+        session_start: session_start.map(|time| (time - start).as_millis()),
+        pub_sub_worker_start: pub_sub_worker_start.map(|time| (time - start).as_millis()),
+        before_sending: before_sending.as_millis(),
+        start_sending: start_sending.as_millis(),
+        after_sending: after_sending.as_millis(),
+        sent_msg_num,
+    };
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&pub_time_status).unwrap()
+    )
+    .unwrap();
+
+    Ok(())
+}
+
+/// Publishes the messages of publisher `peer_id` on `key_expr`, paced by `pacer` until
+/// `send_until`. Returns the number of messages sent.
+async fn throughput_publish(
+    session: &Session,
+    key_expr: &str,
+    msg_payload: &[u8],
+    peer_id: usize,
+    config: &WorkerConfig,
+    pacer: &mut Pacer,
+) -> usize {
+    let send_until = config.send_until();
+    let congestion_control = config.publisher_qos.congestion_control(peer_id);
+    let priority = config.publisher_qos.priority(peer_id);
+    let mut msg_id = 0;
+    while Instant::now() < send_until {
+        if let Some(deadline) = pacer.next_deadline() {
+            if deadline >= send_until {
+                break;
+            }
+            pacer.sleep_until(deadline).await;
+        }
+        let mut payload = msg_payload.to_vec();
+        stamp_msg_payload(&mut payload, msg_id);
+        session
+            .put(key_expr, payload)
+            .congestion_control(congestion_control)
+            .priority(priority)
+            .await
+            .unwrap();
+        msg_id += 1;
+    }
+    msg_id
+}
+
+/// Receives the messages of all the publishers and sends the report of subscriber `peer_id` to
+/// `tx`, or sends queries (pings) in query (ping-pong) mode.
+pub async fn subscribe_worker(
+    zenoh: Arc<Session>,
+    peer_id: usize,
+    tx: flume::Sender<SubReport>,
+    config: WorkerConfig,
+    session_start_time: Option<Instant>,
+    pub_sub_worker_start: Option<Instant>,
+) -> Result<()> {
+    match config.test_mode {
+        TestMode::PubSub => {}
+        TestMode::Query => {
+            return query_worker(
+                zenoh,
+                peer_id,
+                tx,
+                config,
+                session_start_time,
+                pub_sub_worker_start,
+            )
+            .await
+        }
+        TestMode::PingPong => {
+            return ping_worker(
+                zenoh,
+                peer_id,
+                tx,
+                config,
+                session_start_time,
+                pub_sub_worker_start,
+            )
+            .await
+        }
+    }
+    let start = config.start;
+    let timeout = config.timeout();
+    let start_worker = Instant::now() - start;
+    // The throughput windows start when the publishers start sending.
+    let mut report = SubReport::new(
+        peer_id,
+        ThroughputMeter::new(
+            now_nanos()
+                + config
+                    .start_until()
+                    .saturating_duration_since(Instant::now())
+                    .as_nanos() as u64,
+        ),
+    );
+    let mut session_start = session_start_time;
+
+    if config.start_until() < Instant::now() {
+        warn!("Subscriber is not initialized after the initial time has passed. Please increase initialization time");
+        tx.send_async(report).await.unwrap();
+        return Ok(());
+    }
+    let zenoh_new = if config.multipeer_mode {
+        let zenoh_new = config
+            .open_worker_session(SessionRole::Subscriber(peer_id))
+            .await;
+        session_start = Some(Instant::now());
+        Some(zenoh_new)
+    } else {
+        None
+    };
+    let after_subscribing;
+    let start_receiving;
+    let after_receiving;
+    {
+        let session = zenoh_new.as_ref().unwrap_or(&*zenoh);
+        let mut subscribers = vec![];
+        for key_expr in config.key_space().sub_key_exprs(peer_id) {
+            subscribers.push(session.subscribe(&key_expr).await.unwrap());
+        }
+        after_subscribing = Instant::now() - start;
+        let stream = futures::stream::select_all(
+            subscribers
+                .iter_mut()
+                .map(|subscriber| subscriber.receiver()),
+        );
+        start_receiving = Instant::now() - start;
+        let round_end = async_std::task::sleep(timeout.saturating_duration_since(Instant::now()));
+        futures::pin_mut!(round_end);
+        let mut received = stream
+            .take(if config.is_throughput_mode() {
+                usize::MAX
+            } else {
+                config.expected_msg_num(peer_id)
+            })
+            .take_until(round_end);
+        while let Some(sample) = received.next().await {
+            track_received(&sample, now_nanos(), &config, &mut report);
+        }
+        after_receiving = Instant::now() - start;
+    }
+    tx.send_async(report).await.unwrap();
+    if let Some(zenoh_new) = zenoh_new {
+        zenoh_new.close().await.unwrap();
+    }
+
+    let sub_time_status = SubTimeStatus {
+        process_start_sec: config
+            .process_start
+            .map(|time| time.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64),
+        process_start_millis: config
+            .process_start
+            .map(|time| time.duration_since(UNIX_EPOCH).unwrap().subsec_millis() as i16),
+        start_sub_worker: start_worker.as_millis(),
+        session_start: session_start.map(|time| (time - start).as_millis()),
+        pub_sub_worker_start: pub_sub_worker_start.map(|time| (time - start).as_millis()),
+        after_subscribing: after_subscribing.as_millis(),
+        start_receiving: start_receiving.as_millis(),
+        after_receiving: after_receiving.as_millis(),
+    };
+    let file_path =
+        config
+            .output_dir
+            .join(format!("sub_{}_info_{}.json", peer_id, config.exp_name()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)
+        .unwrap();
+    writeln!(
+        &mut file,
+        "{}",
+        serde_json::to_string_pretty(&sub_time_status).unwrap()
+    )
+    .unwrap();
+
+    Ok(())
+}
+
+/// Folds a sample received by a subscriber at `recv_nanos` into its report.
+fn track_received(sample: &Sample, recv_nanos: u64, config: &WorkerConfig, report: &mut SubReport) {
+    let mut unique = false;
+    match MsgHeader::decode(&sample.value.payload.contiguous()) {
+        Ok(header) => {
+            unique = report
+                .seq_trackers
+                .entry(header.peer_id as usize)
+                .or_insert_with(|| SeqTracker::with_limit(config.seq_limit()))
+                .record(header.seq);
+            // The duplicates and the out-of-range messages would skew the latencies.
+            if unique {
+                let latency = header.latency(recv_nanos);
+                report.latency.record(latency);
+                if let Some(class) = config.publisher_qos.class(header.peer_id as usize) {
+                    report
+                        .class_latency
+                        .entry(class.to_string())
+                        .or_default()
+                        .record(latency);
+                }
+            }
+        }
+        Err(err) => debug!("Received a malformed payload: {}", err),
+    }
+    report.record_sample(recv_nanos, sample.value.payload.len(), unique);
+}
+
+pub async fn pub_and_sub_worker(
+    peer_id: usize,
+    tx: flume::Sender<SubReport>,
+    config: WorkerConfig,
+) -> Result<()> {
+    let pub_sub_worker_start = Some(Instant::now());
+    let zenoh = Arc::new(
+        config
+            .open_worker_session(SessionRole::PubSub(peer_id))
+            .await,
+    );
+    let session_start_time = Some(Instant::now());
+    let worker_config = WorkerConfig {
+        multipeer_mode: false,
+        ..config
+    };
+    let pub_future = publish_worker(
+        zenoh.clone(),
+        peer_id,
+        worker_config.clone(),
+        session_start_time,
+        pub_sub_worker_start,
+    );
+    let sub_future = subscribe_worker(
+        zenoh.clone(),
+        peer_id,
+        tx,
+        worker_config,
+        session_start_time,
+        pub_sub_worker_start,
+    );
+    futures::try_join!(pub_future, sub_future)?;
+    let zenoh = Arc::try_unwrap(zenoh).map_err(|_| ()).unwrap();
+    zenoh.close().await.unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use crate::{common::*, config::SessionRole};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The zenoh config files of an experiment: the base config of every session, and the files
+/// overriding it for the publishers, the subscribers and the routers.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ZenohConfigFiles {
+    pub base: Option<PathBuf>,
+    pub publisher: Option<PathBuf>,
+    pub subscriber: Option<PathBuf>,
+    pub router: Option<PathBuf>,
+}
+
+/// The effective config of the first session opened for a kind of role (e.g. the first
+/// publisher). The other sessions of the kind share its config but for their endpoints.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionConfigRecord {
+    pub role: SessionRole,
+    pub config: Value,
+    /// The number of sessions opened for this kind of role.
+    #[serde(default)]
+    pub session_num: usize,
+}
+
+/// The loaded config files of an experiment. The clones share the records of the sessions
+/// opened with them.
+#[derive(Debug, Clone, Default)]
+pub struct ZenohConfigs {
+    base: Option<Config>,
+    /// The (key, JSON value) pairs each override file sets.
+    publisher: Vec<(String, String)>,
+    subscriber: Vec<(String, String)>,
+    router: Vec<(String, String)>,
+    records: Arc<Mutex<Vec<SessionConfigRecord>>>,
+}
+
+fn read_config(path: &Path) -> Result<Config> {
+    Config::from_file(path).map_err(|err| anyhow::anyhow!("{}: {}", path.display(), err))
+}
+
+/// The leaves of `value`, as (key, JSON value) pairs. The arrays are leaves, set as a whole.
+fn flatten_leaves(value: &Value, key: &str, leaves: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                let field_key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}/{}", key, name)
+                };
+                flatten_leaves(field, &field_key, leaves);
+            }
+        }
+        _ => leaves.push((key.to_string(), value.to_string())),
+    }
+}
+
+/// The keys an override file sets, whatever their value, so that an override may also set a key
+/// back to its zenoh default. The file is checked by zenoh and read as JSON5 or YAML by its
+/// extension, like the base file.
+fn read_overrides(path: &Option<PathBuf>) -> Result<Vec<(String, String)>> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(vec![]),
+    };
+    read_config(path)?;
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("{}: {}", path.display(), err))?;
+    let value: Value = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&text)?,
+        _ => json5::from_str(&text)?,
+    };
+    let mut overrides = vec![];
+    flatten_leaves(&value, "", &mut overrides);
+    Ok(overrides)
+}
+
+impl ZenohConfigs {
+    /// Reads the config files, and checks that the overrides apply to the base config.
+    pub fn load(files: &ZenohConfigFiles) -> Result<Self> {
+        let configs = ZenohConfigs {
+            base: files.base.as_deref().map(read_config).transpose()?,
+            publisher: read_overrides(&files.publisher)?,
+            subscriber: read_overrides(&files.subscriber)?,
+            router: read_overrides(&files.router)?,
+            records: Arc::new(Mutex::new(vec![])),
+        };
+        for overrides in [&configs.publisher, &configs.subscriber, &configs.router] {
+            let mut config = configs.base();
+            apply_overrides(&mut config, overrides)?;
+        }
+        Ok(configs)
+    }
+
+    fn base(&self) -> Config {
+        self.base.clone().unwrap_or_else(config::default)
+    }
+
+    /// The config of a session opened for `role`, before the harness adds its endpoints: the base
+    /// config with the overrides of the role. The sessions both publishing and subscribing get the
+    /// publisher overrides, then the subscriber ones.
+    pub fn config(&self, role: SessionRole) -> Config {
+        let overrides: &[&Vec<(String, String)>] = match role {
+            SessionRole::Publisher(_) => &[&self.publisher],
+            SessionRole::Subscriber(_) => &[&self.subscriber],
+            SessionRole::PubSub(_) | SessionRole::Listener => &[&self.publisher, &self.subscriber],
+            SessionRole::Router(_) => &[&self.router],
+        };
+        let mut config = self.base();
+        for overrides in overrides {
+            // Checked by `load`.
+            apply_overrides(&mut config, overrides).unwrap();
+        }
+        config
+    }
+
+    /// Records the effective config of a session of the experiment, if it is the first one of its
+    /// kind of role, and counts it.
+    pub fn record(&self, role: SessionRole, config: &Config) {
+        let mut records = self.records.lock().unwrap();
+        let kind = std::mem::discriminant(&role);
+        match records
+            .iter_mut()
+            .find(|record| std::mem::discriminant(&record.role) == kind)
+        {
+            Some(record) => record.session_num += 1,
+            None => records.push(SessionConfigRecord {
+                role,
+                config: serde_json::to_value(config).unwrap(),
+                session_num: 1,
+            }),
+        }
+    }
+
+    /// The effective config of every kind of session opened so far.
+    pub fn records(&self) -> Vec<SessionConfigRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+fn apply_overrides(config: &mut Config, overrides: &[(String, String)]) -> Result<()> {
+    for (key, value) in overrides {
+        config
+            .insert_json5(key, value)
+            .map_err(|err| anyhow::anyhow!("cannot set {} to {}: {}", key, value, err))?;
+    }
+    Ok(())
+}
+
+/// Adds `endpoints` to the ones the session connects to, e.g. in the base config.
+pub fn add_connect(config: &mut Config, endpoints: impl IntoIterator<Item = EndPoint>) {
+    let mut connect = config.connect().endpoints.clone();
+    connect.extend(endpoints);
+    config
+        .set_connect(ConnectConfig { endpoints: connect })
+        .unwrap();
+}
+
+/// Adds `endpoints` to the ones the session listens on, e.g. in the base config.
+pub fn add_listen(config: &mut Config, endpoints: impl IntoIterator<Item = EndPoint>) {
+    let mut listen = config.listen().endpoints.clone();
+    listen.extend(endpoints);
+    config
+        .set_listen(ListenConfig { endpoints: listen })
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_keep_every_key_set() {
+        // `batch_size` and `lease` set to their defaults must still override the base file.
+        let value: Value = json5::from_str(
+            "{ transport: { unicast: { lease: 10000 }, link: { tx: { batch_size: 65535 } } }, \
+               connect: { endpoints: ['tcp/127.0.0.1:7447'] }, scouting: {} }",
+        )
+        .unwrap();
+        let mut overrides = vec![];
+        flatten_leaves(&value, "", &mut overrides);
+        overrides.sort();
+        assert_eq!(
+            overrides,
+            vec![
+                (
+                    "connect/endpoints".to_string(),
+                    "[\"tcp/127.0.0.1:7447\"]".to_string()
+                ),
+                (
+                    "transport/link/tx/batch_size".to_string(),
+                    "65535".to_string()
+                ),
+                ("transport/unicast/lease".to_string(), "10000".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_overrides() {
+        let value: Value =
+            serde_yaml::from_str("transport:\n  unicast:\n    lease: 1000\n").unwrap();
+        let mut overrides = vec![];
+        flatten_leaves(&value, "", &mut overrides);
+        assert_eq!(
+            overrides,
+            vec![("transport/unicast/lease".to_string(), "1000".to_string())]
+        );
+    }
+
+    #[test]
+    fn one_record_per_kind_of_role() {
+        let configs = ZenohConfigs::default();
+        let config = configs.config(SessionRole::Publisher(0));
+        for peer_id in 0..10 {
+            configs.record(SessionRole::Publisher(peer_id), &config);
+            configs
+                .clone()
+                .record(SessionRole::Subscriber(peer_id), &config);
+        }
+        configs.record(SessionRole::Listener, &config);
+        let records = configs.records();
+        let kinds = records
+            .iter()
+            .map(|record| (record.role, record.session_num))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (SessionRole::Publisher(0), 10),
+                (SessionRole::Subscriber(0), 10),
+                (SessionRole::Listener, 1)
+            ]
+        );
+    }
+}